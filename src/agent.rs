@@ -1,16 +1,17 @@
 #![allow(unused)]
-use std::collections::hash_map::{Iter, IterMut};
-use std::collections::HashMap;
+use std::collections::btree_map::{Iter, IterMut};
+use std::collections::BTreeMap;
 use std::f32::consts::PI;
 
 use macroquad::{color, prelude::*};
+use crate::config::{BoundaryMode, SimConfig};
 use crate::consts::*;
+use crate::genome::*;
 use crate::kinetic::make_isometry;
-use crate::neuro::*;
 use crate::timer::*;
 use crate::util::*;
 use crate::world::*;
-use ::rand::{thread_rng, Rng};
+use ::rand::{Rng, rngs::StdRng};
 use rapier2d::geometry::*;
 use rapier2d::prelude::RigidBodyHandle;
 
@@ -18,8 +19,11 @@ pub struct Agent {
     pub key: u64,
     pub pos: Vec2,
     pub rot: f32,
+    prev_pos: Vec2,
+    prev_rot: f32,
     pub vel: f32,
     pub ang_vel: f32,
+    pub mass: f32,
     pub size: f32,
     pub vision_range: f32,
     pub max_eng: f32,
@@ -31,63 +35,87 @@ pub struct Agent {
     motor_phase: f32,
     motor_phase2: f32,
     motor_side: bool,
-    analize_timer: Timer,
-    analizer: DummyNetwork,
+    pub analize_timer: Timer,
+    pub mate_timer: Timer,
+    pub mate_ready: bool,
+    pub genome: Genome,
     pub alife: bool,
     //enemy: Detection,
     pub detected: Option<Detected>,
     pub enemy: Option<RigidBodyHandle>,
     pub enemy_position: Option<Vec2>,
     pub enemy_dir: Option<f32>,
+    pub contacts: Vec<u64>,
+    pub attacking: bool,
     pub physics_handle: Option<RigidBodyHandle>,
 }
 
 impl Agent {    
-    pub fn new() -> Self {
-        let s = rand::gen_range(AGENT_SIZE_MIN, AGENT_SIZE_MAX) as f32;
-        let motor = thread_rng().gen_bool(1.0);
-        let p = thread_rng().gen_range(0.2..0.8);
+    pub fn new(config: &SimConfig, rng: &mut StdRng) -> Self {
+        let genome = Genome::random(config, rng);
+        return Agent::from_genome(genome, config, rng);
+    }
 
+    pub fn from_genome(genome: Genome, config: &SimConfig, rng: &mut StdRng) -> Self {
+        let s = genome.size;
+        let p = rng.gen_range(0.2..0.8);
+
+        let pos = random_position(config.world_width, config.world_height, rng);
+        let rot = random_rotation(rng);
         Self {
-            key: thread_rng().gen::<u64>(),
-            pos: random_position(WORLD_W, WORLD_H),
-            rot: random_rotation(),
-            vel: rand::gen_range(0.0, 1.0) * AGENT_SPEED,
+            key: rng.gen::<u64>(),
+            pos,
+            rot,
+            prev_pos: pos,
+            prev_rot: rot,
+            vel: rng.gen_range(0.0..1.0) * genome.speed,
             ang_vel: 0.0,
+            mass: 0.0,
             size: s,
-            vision_range: (rand::gen_range(0.5, 1.5) * AGENT_VISION_RANGE).round(),
-            max_eng: s.powi(2) * 10.0,
-            eng: s.powi(2) * 10.0,
-            color: random_color(),
-            pulse: rand::gen_range(0.0, 1.0),
+            vision_range: genome.vision_range,
+            max_eng: s.powi(2) * config.agent_eng_capacity,
+            eng: s.powi(2) * config.agent_eng_capacity,
+            color: genome.color,
+            pulse: rng.gen_range(0.0..1.0),
             shape: Ball { radius: s },
-            motor: motor,
+            motor: genome.motor,
             motor_phase: p,
             motor_phase2: p,
             motor_side: true,
-            analize_timer: Timer::new(0.3, true, true, true),
-            analizer: DummyNetwork::new(2),
+            analize_timer: Timer::new(config.agent_analyze_interval, true, true, rng.gen_range(0.0..config.agent_analyze_interval)),
+            mate_timer: Timer::new(config.mate_cooldown, false, true, rng.gen_range(0.0..=config.mate_cooldown)),
+            mate_ready: false,
             alife: true,
             detected: None,
             enemy: None,
             enemy_position: None,
             enemy_dir: None,
+            contacts: vec![],
+            attacking: false,
             //enemy: Detection::new_empty(),
             physics_handle: None,
+            genome,
         }
     }
-    
-    pub fn draw(&self, field_of_view: bool) {
-        //let dir = Vec2::from_angle(self.rot);
-        let x0 = self.pos.x;
-        let y0 = self.pos.y;
+
+    pub fn render_pose(&self, alpha: f32) -> (Vec2, f32) {
+        return lerp_pose(self.prev_pos, self.pos, self.prev_rot, self.rot, alpha);
+    }
+
+    //  offset moves the drawing onto a ghost copy across a seam
+    pub fn draw(&self, field_of_view: bool, alpha: f32, offset: Vec2) {
+        let (pos, rot) = self.render_pose(alpha);
+        let pos = pos + offset;
+        //let dir = Vec2::from_angle(rot);
+        let x0 = pos.x;
+        let y0 = pos.y;
         //let x1 = x0 + dir.x * self.size * 1.0;
         //let y1 = y0 + dir.y * self.size * 1.0;
         //let x2 = x0 + dir.x * self.size * 2.0;
         //let y2 = y0 + dir.y * self.size * 2.0;
         if self.motor {
-            let tail = Vec2::from_angle(self.rot + (self.motor_phase * 0.5));
-            //let tail2 = Vec2::from_angle(self.rot + (self.motor_phase2 * 0.5));
+            let tail = Vec2::from_angle(rot + (self.motor_phase * 0.5));
+            //let tail2 = Vec2::from_angle(rot + (self.motor_phase2 * 0.5));
             let x3 = x0 - tail.x * self.size * 1.4;
             let y3 = y0 - tail.y * self.size * 1.4;
             //let x4 = x0 - tail2.x * self.size * 2.4;
@@ -97,10 +125,10 @@ impl Agent {
             //draw_line(x4, y4, x3, y3, self.size / 2.0, self.color)
         }
         let pulse = (self.pulse * 2.0) - 1.0;
-        self.draw_target();
+        self.draw_target(pos, rot, offset);
         draw_circle_lines(x0, y0, self.size, 2.0, self.color);
         draw_circle(x0, y0, (self.size / 2.0) * pulse.abs(), self.color);
-        self.draw_front();
+        self.draw_front(pos, rot);
         //draw_line(x1, y1, x2, y2, 1.0, self.color);
         //draw_text(&self.key.to_string(), x0-80.0, y0-self.size*2.0, 20.0, WHITE);
         if field_of_view {
@@ -108,83 +136,77 @@ impl Agent {
         }
     }
 
-    fn draw_front(&self) {
-        let dir = Vec2::from_angle(self.rot);
-        let v0l = Vec2::from_angle(self.rot-PI/2.0)*self.size;
-        let v0r = Vec2::from_angle(self.rot+PI/2.0)*self.size;
-        let x0l = self.pos.x+v0l.x;
-        let y0l = self.pos.y+v0l.y;
-        let x0r = self.pos.x+v0r.x;
-        let y0r = self.pos.y+v0r.y;
-        let x2 = self.pos.x + dir.x * self.size * 2.0;
-        let y2 = self.pos.y + dir.y * self.size * 2.0;
-        draw_line(x0l, y0l, x2, y2, 2.0, self.color);
-        draw_line(x0r, y0r, x2, y2, 2.0, self.color);        
+    fn draw_front(&self, pos: Vec2, rot: f32) {
+        let dir = Vec2::from_angle(rot);
+        let v0l = Vec2::from_angle(rot-PI/2.0)*self.size;
+        let v0r = Vec2::from_angle(rot+PI/2.0)*self.size;
+        let x0l = pos.x+v0l.x;
+        let y0l = pos.y+v0l.y;
+        let x0r = pos.x+v0r.x;
+        let y0r = pos.y+v0r.y;
+        let x2 = pos.x + dir.x * self.size * 2.0;
+        let y2 = pos.y + dir.y * self.size * 2.0;
+        let color = if self.attacking { RED } else { self.color };
+        draw_line(x0l, y0l, x2, y2, 2.0, color);
+        draw_line(x0r, y0r, x2, y2, 2.0, color);        
     }
 
-    fn draw_target(&self) {
+    fn draw_target(&self, pos: Vec2, rot: f32, offset: Vec2) {
         //if !self.enemy.is_none() {
         if let Some(_rb) = self.enemy {
             if let Some(enemy_position) = self.enemy_position {
-                let v0l = Vec2::from_angle(self.rot-PI/2.0)*self.size;
-                let v0r = Vec2::from_angle(self.rot+PI/2.0)*self.size;
-                let x0l = self.pos.x+v0l.x;
-                let y0l = self.pos.y+v0l.y;
-                let x0r = self.pos.x+v0r.x;
-                let y0r = self.pos.y+v0r.y;
-                let x1 = enemy_position.x;
-                let y1 = enemy_position.y;
+                let v0l = Vec2::from_angle(rot-PI/2.0)*self.size;
+                let v0r = Vec2::from_angle(rot+PI/2.0)*self.size;
+                let x0l = pos.x+v0l.x;
+                let y0l = pos.y+v0l.y;
+                let x0r = pos.x+v0r.x;
+                let y0r = pos.y+v0r.y;
+                let x1 = enemy_position.x + offset.x;
+                let y1 = enemy_position.y + offset.y;
                 draw_line(x0l, y0l, x1, y1, 0.75, self.color);
                 draw_line(x0r, y0r, x1, y1, 0.75, self.color);
             }
         }
     }
 
-    pub fn update2(&mut self, physics: &mut World) {
+    pub fn update2(&mut self, physics: &mut World, bounds: &WorldBounds) {
         match self.physics_handle {
             Some(handle) => {
-                self.update_enemy_position(physics);
+                self.update_enemy_position(physics, bounds);
+                self.contacts = physics.get_contacts(self.key);
                 let physics_data = physics.get_physics_data(handle);
+                self.prev_pos = self.pos;
+                self.prev_rot = self.rot;
                 self.pos = physics_data.position;
                 self.rot = physics_data.rotation;
-                match physics.rigid_bodies.get_mut(handle) {
+                self.mass = physics_data.mass;
+                let dir = Vec2::from_angle(self.rot);
+                let v = dir * self.vel;
+                let raw_pos = match physics.rigid_bodies.get_mut(handle) {
                     Some(body) => {
-                        let dir = Vec2::from_angle(self.rot);
-                        let v = dir * self.vel;
                         body.set_linvel([v.x, v.y].into(), true);
                         body.set_angvel(self.ang_vel, true);
-                        let mut raw_pos = matric_to_vec2(body.position().translation);
-                        let mut out_of_edge = false;
-                        if raw_pos.x < 0.0 {
-                            raw_pos.x = 0.0;
-                            out_of_edge = true;
-                        } else if raw_pos.x > WORLD_W {
-                            raw_pos.x = WORLD_W;
-                            out_of_edge = true;
-                        }
-                        if raw_pos.y < 0.0 {
-                            raw_pos.y = 0.0;
-                            out_of_edge = true;
-                        } else if raw_pos.y > WORLD_H {
-                            raw_pos.y = WORLD_H;
-                            out_of_edge = true;
-                        }
-                        if out_of_edge {
-                            body.set_position(make_isometry(raw_pos.x, raw_pos.y, self.rot), true);
-                        }
+                        matric_to_vec2(body.position().translation)
+                    }
+                    None => return,
+                };
+                if let Some((pos, vel)) = bounds.confine(raw_pos, v) {
+                    if bounds.mode == BoundaryMode::Bounce && vel != v {
+                        self.rot = vel.y.atan2(vel.x);
                     }
-                    None => {}
+                    physics.set_body_state(handle, pos, self.rot, vel, self.ang_vel);
                 }
             }
             None => {}
         }
     }
 
-    fn update_enemy_position(&mut self, physics: &World) {
+    //  enemy_position keeps the image of the target nearest to the agent, so it can lie past a seam
+    fn update_enemy_position(&mut self, physics: &World, bounds: &WorldBounds) {
         if let Some(rb) = self.enemy {
             if let Some(enemy_position) = physics.get_object_position(rb) {
-                self.enemy_position = Some(enemy_position);
-                let rel_pos = enemy_position - self.pos;
+                let rel_pos = bounds.delta(self.pos, enemy_position);
+                self.enemy_position = Some(self.pos + rel_pos);
                 let enemy_dir = rel_pos.angle_between(Vec2::from_angle(self.rot));
                 self.enemy_dir = Some(enemy_dir);
             } else { 
@@ -198,13 +220,13 @@ impl Agent {
         }
     }
 
-    pub fn update(&mut self, dt: f32, physics: &World) -> bool {
+    pub fn update(&mut self, dt: f32, metabolism: f32, physics: &World, bounds: &WorldBounds) -> bool {
         if self.analize_timer.update(dt) {
             match self.physics_handle {
                 Some(handle) => {
-                    if let Some(tg) = physics.get_closesd_agent(handle) {
+                    if let Some(tg) = physics.get_closesd_agent(handle, bounds) {
                         self.enemy = Some(tg);
-                        self.update_enemy_position(physics);
+                        self.update_enemy_position(physics, bounds);
                     } else {
                         self.enemy = None;
                         self.enemy_position = None;
@@ -213,13 +235,18 @@ impl Agent {
                 },
                 None => {},
             }
-            let outputs = self.analizer.analize();
+            let inputs = self.sense();
+            let outputs = self.genome.brain.analize(&inputs);
             if outputs[0] >= 0.0 {
-                self.vel = outputs[0] * AGENT_SPEED;
+                self.vel = outputs[0] * self.genome.speed;
             } else {
                 self.vel = 0.0;
             }
-            self.ang_vel = outputs[1] * AGENT_ROTATION;
+            self.ang_vel = outputs[1] * self.genome.turn_rate;
+            self.attacking = outputs[2] > 0.0;
+        }
+        if !self.mate_ready && self.mate_timer.update(dt) {
+            self.mate_ready = true;
         }
         self.pulse = (self.pulse + dt * 0.25) % 1.0;
         if self.motor {
@@ -241,7 +268,7 @@ impl Agent {
             }
         }
         if self.eng > 0.0 {
-            self.eng -= self.size * 1.0 * dt;
+            self.eng -= self.size * metabolism * dt;
         } else {
             self.eng = 0.0;
             self.alife = false;
@@ -249,6 +276,74 @@ impl Agent {
         return self.alife;
     }
 
+    fn sense(&self) -> Vec<f32> {
+        let enemy_dir = match self.enemy_dir {
+            Some(dir) => dir / PI,
+            None => 0.0,
+        };
+        let enemy_dist = match self.enemy_position {
+            Some(enemy_position) => {
+                let dist = self.pos.distance(enemy_position);
+                (1.0 - dist / self.vision_range).clamp(0.0, 1.0)
+            },
+            None => 0.0,
+        };
+        return vec![
+            enemy_dir,
+            enemy_dist,
+            self.eng / self.max_eng,
+            self.vel / self.genome.speed,
+            self.ang_vel / self.genome.turn_rate,
+        ];
+    }
+
+    pub fn can_replicate(&self, eng_threshold: f32) -> bool {
+        return self.alife && self.eng >= self.max_eng * eng_threshold;
+    }
+
+    //  the parent only pays once the offspring is accepted, see `commit_replication`
+    pub fn replicate(&self, config: &SimConfig, bounds: &WorldBounds, rng: &mut StdRng) -> Agent {
+        let mut genome = self.genome.clone();
+        genome.mutate(config, rng);
+        let mut offspring = Agent::from_genome(genome, config, rng);
+        offspring.eng = self.replication_cost(config).min(offspring.max_eng);
+        let pos = self.pos + random_unit_vec2(rng) * (self.size + offspring.size) * 1.5;
+        offspring.pos = match bounds.confine(pos, Vec2::ZERO) {
+            Some((pos, _)) => pos,
+            None => pos,
+        };
+        return offspring;
+    }
+
+    pub fn replication_cost(&self, config: &SimConfig) -> f32 {
+        return self.max_eng * config.repro_eng_cost;
+    }
+
+    pub fn commit_replication(&mut self, cost: f32) {
+        self.eng -= cost;
+    }
+
+    pub fn wants_mate(&self, min_eng: f32) -> bool {
+        return self.alife && self.mate_ready && self.eng >= self.max_eng * min_eng;
+    }
+
+    pub fn mated(&mut self, eng_cost: f32, cooldown: f32) -> f32 {
+        let cost = self.max_eng * eng_cost;
+        self.eng -= cost;
+        self.mate_ready = false;
+        self.mate_timer.set_duration(cooldown);
+        self.mate_timer.restart();
+        return cost;
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        return Vec2::from_angle(self.rot) * self.vel;
+    }
+
+    pub fn take_damage(&mut self, damage: f32) {
+        self.eng = (self.eng - damage).max(0.0);
+    }
+
     pub fn add_energy(&mut self, e: f32) {
         self.eng += e;
         if self.eng > self.max_eng {
@@ -259,19 +354,19 @@ impl Agent {
 }
 
 pub struct AgentsBox {
-    pub agents: HashMap<u64, Agent>,
+    pub agents: BTreeMap<u64, Agent>,
 }
 
 impl AgentsBox {
     pub fn new() -> Self {
         Self {
-            agents: HashMap::new(),
+            agents: BTreeMap::new(),
         }
     }
 
-    pub fn add_many_agents(&mut self, agents_num: usize, physics_world: &mut World) {
+    pub fn add_many_agents(&mut self, agents_num: usize, physics_world: &mut World, config: &SimConfig, rng: &mut StdRng) {
         for _ in 0..agents_num {
-            let agent = Agent::new();
+            let agent = Agent::new(config, rng);
             _ = self.add_agent(agent, physics_world);
         }
     }
@@ -280,6 +375,8 @@ impl AgentsBox {
         let key = agent.key;
         let handle = physics_world.add_circle_body(key,&agent.pos, agent.size, Some(agent.vision_range));
         agent.physics_handle = Some(handle);
+        agent.prev_pos = agent.pos;
+        agent.prev_rot = agent.rot;
         self.agents.insert(key, agent);
         return key;
     }
@@ -0,0 +1,270 @@
+#![allow(unused)]
+
+use crate::camera::*;
+use crate::config::*;
+use crate::consts::*;
+use crate::editor::*;
+use crate::element::*;
+use crate::kinetic::*;
+use crate::sim::*;
+use crate::ui::*;
+use macroquad::camera::Camera2D;
+use macroquad::prelude::*;
+use std::f32::consts::PI;
+use std::time::Instant;
+
+//  windowed front-end: owns everything that needs macroquad's context and drives the simulation core
+pub struct App {
+    pub sim: Simulation,
+    pub font: Font,
+    pub camera: Camera2D,
+    camera_world: Vec2,
+    pub ui: UISystem,
+    pub speed: SimSpeed,
+    select_phase: f32,
+    pub selected: u64,
+    pub mouse_state: MouseState,
+    pub config_watcher: Option<ConfigWatcher>,
+    pub editor: MapEditor,
+    accumulator: f32,
+}
+
+impl App {
+    pub fn new(configuration: SimConfig, font: Font) -> Self {
+        let sim = Simulation::new(configuration);
+        let camera_world = sim.world_size;
+        Self {
+            sim,
+            font,
+            camera: create_camera(camera_world),
+            camera_world,
+            ui: UISystem::new(),
+            speed: SimSpeed::X1,
+            select_phase: 0.0,
+            selected: 0,
+            mouse_state: MouseState { pos: Vec2::NAN },
+            config_watcher: None,
+            editor: MapEditor::new(),
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn update(&mut self) {
+        if let Some(speed) = self.sim.signals.sim_speed.take() {
+            self.speed = speed;
+        }
+        self.sim.signals_check();
+        if let Some(watcher) = self.config_watcher.as_mut() {
+            self.sim.reload_config(watcher);
+        }
+        if self.camera_world != self.sim.world_size {
+            self.camera_world = self.sim.world_size;
+            self.camera = create_camera(self.camera_world);
+        }
+        self.update_frame_state();
+        self.calc_selection_time();
+        if !self.sim.is_running() {
+            return;
+        }
+        match self.speed.multiplier() {
+            Some(multiplier) => {
+                self.accumulator += get_frame_time() * multiplier as f32;
+                let mut steps = 0;
+                while self.accumulator >= FIX_DT {
+                    if steps >= MAX_STEPS_PER_FRAME * multiplier {
+                        //  too slow to keep up: drop the backlog instead of spiraling
+                        self.accumulator = 0.0;
+                        break;
+                    }
+                    self.sim.step();
+                    self.accumulator -= FIX_DT;
+                    steps += 1;
+                }
+            },
+            None => {
+                //  as many steps as fit in one frame's time budget
+                self.accumulator = 0.0;
+                let start = Instant::now();
+                loop {
+                    self.sim.step();
+                    if start.elapsed().as_secs_f32() >= MAX_SPEED_BUDGET {
+                        break;
+                    }
+                }
+            },
+        }
+    }
+
+    pub fn draw(&self) {
+        //set_default_camera();
+        set_camera(&self.camera);
+        clear_background(BLACK);
+        draw_rectangle_lines(0.0, 0.0, self.sim.world_size.x, self.sim.world_size.y, 3.0, WHITE);
+        if let Some(field) = self.sim.nutrients.as_ref() {
+            field.draw();
+        }
+        self.draw_grid(50);
+        self.draw_obstacles();
+        let alpha = self.accumulator / FIX_DT;
+        self.draw_sources();
+        self.draw_agents(alpha);
+        self.draw_elements(alpha);
+        if self.ui.state.editor {
+            self.editor.draw(&self.sim, self.world_cursor());
+        }
+    }
+
+    fn draw_agents(&self, alpha: f32) {
+        let bounds = self.sim.bounds();
+        for (id, agent) in self.sim.agents.get_iter() {
+            let mut draw_field_of_view: bool = false;
+            if *id == self.selected {
+                draw_field_of_view = true;
+            };
+            let margin = if draw_field_of_view { agent.vision_range } else { agent.size * 2.0 };
+            agent.draw(draw_field_of_view, alpha, Vec2::ZERO);
+            for offset in bounds.ghost_offsets(agent.pos, margin) {
+                agent.draw(draw_field_of_view, alpha, offset);
+            }
+        }
+        match self.sim.agents.get(self.selected) {
+            Some(selected_agent) => {
+                let (pos, _) = selected_agent.render_pose(alpha);
+                let s = selected_agent.size;
+                draw_circle_lines(
+                    pos.x,
+                    pos.y,
+                    2.0 * s + (self.select_phase.sin() * s * 0.5),
+                    1.0,
+                    ORANGE,
+                );
+            }
+            None => {}
+        };
+    }
+
+    fn draw_obstacles(&self) {
+        for (_, obstacle) in self.sim.obstacles.get_iter() {
+            obstacle.draw();
+        }
+    }
+
+    fn draw_sources(&self) {
+        let bounds = self.sim.bounds();
+        for (id, source) in self.sim.sources.get_iter() {
+            source.draw(Vec2::ZERO);
+            for offset in bounds.ghost_offsets(source.pos, source.size) {
+                source.draw(offset);
+            }
+        }
+    }
+
+    fn draw_elements(&self, alpha: f32) {
+        let bounds = self.sim.bounds();
+        for (id, element) in self.sim.elements.get_iter() {
+            element.draw(self.font, alpha, Vec2::ZERO);
+            for offset in bounds.ghost_offsets(element.pos, element.size * 2.0) {
+                element.draw(self.font, alpha, offset);
+            }
+        }
+    }
+
+    fn draw_grid(&self, cell_size: u32) {
+        let w = self.sim.world_size.x;
+        let h = self.sim.world_size.y;
+        let col_num = (w / cell_size as f32).floor() as u32;
+        let row_num = (h / cell_size as f32).floor() as u32;
+        //draw_grid(100, 20.0, GRAY, DARKGRAY);
+        for x in 0..col_num + 1 {
+            for y in 0..row_num + 1 {
+                draw_circle((x * cell_size) as f32, (y * cell_size) as f32, 1.0, GRAY);
+            }
+        }
+    }
+
+    pub fn input(&mut self) {
+        if self.ui.state.editor {
+            if !self.ui.pointer_over {
+                let cursor = self.world_cursor();
+                self.editor.input(&mut self.sim, cursor, !self.ui.keyboard_over);
+            }
+        } else {
+            self.mouse_input();
+        }
+        if !self.ui.keyboard_over {
+            self.keys_input();
+            control_camera(&mut self.camera, self.sim.world_size);
+        }
+    }
+
+    fn keys_input(&mut self) {
+        if is_key_pressed(KeyCode::Space) {
+            self.sim.signals.pause_sim = true;
+        }
+        if is_key_pressed(KeyCode::Period) {
+            self.sim.signals.step_sim = true;
+        }
+        if is_key_pressed(KeyCode::E) {
+            self.ui.state.editor = !self.ui.state.editor;
+            self.editor.cancel();
+        }
+        let speed_keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5];
+        for (key, speed) in speed_keys.iter().zip(SimSpeed::ALL.iter()) {
+            if is_key_pressed(*key) {
+                self.sim.signals.sim_speed = Some(*speed);
+            }
+        }
+    }
+
+    fn mouse_input(&mut self) {
+        if is_mouse_button_released(MouseButton::Left) {
+            if !self.ui.pointer_over {
+                self.selected = 0;
+                let (mouse_posx, mouse_posy) = mouse_position();
+                let mouse_pos = Vec2::new(mouse_posx, mouse_posy);
+                let rel_coords = self.camera.screen_to_world(mouse_pos);
+                let bounds = self.sim.bounds();
+                for (id, agent) in self.sim.agents.get_iter() {
+                    //  the agent's image nearest to the pointer, so ghosts can be picked too
+                    let target = rel_coords + bounds.delta(rel_coords, agent.pos);
+                    if contact_mouse(rel_coords, target, agent.size) {
+                        self.selected = *id;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn world_cursor(&self) -> Vec2 {
+        let (mouse_x, mouse_y) = mouse_position();
+        return self.camera.screen_to_world(Vec2::new(mouse_x, mouse_y));
+    }
+
+    fn update_frame_state(&mut self) {
+        self.sim.sim_state.fps = get_fps();
+        let (mouse_x, mouse_y) = mouse_position();
+        self.mouse_state.pos = Vec2::new(mouse_x, mouse_y);
+    }
+
+    fn calc_selection_time(&mut self) {
+        self.select_phase += get_frame_time() * 4.0;
+        self.select_phase = self.select_phase % (2.0 * PI as f32);
+    }
+
+    pub fn process_ui(&mut self) {
+        let next_config = self.sim.next_config().clone();
+        let marked_agent = self.sim.agents.get(self.selected);
+        self.ui
+            .ui_process(&self.sim.sim_state, &next_config, self.sim.running, self.speed, marked_agent, &mut self.sim.signals, &self.camera, &mut self.editor);
+    }
+
+    pub fn draw_ui(&self) {
+        self.ui.ui_draw();
+    }
+}
+
+//?         [[[MOUSESTATE]]]
+pub struct MouseState {
+    pub pos: Vec2,
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use crate::config::ConfigOverrides;
+use crate::consts::*;
+
+
+#[derive(Parser)]
+#[command(name = "quad_template", about = "LIVE 2.0 artificial life simulation")]
+pub struct Cli {
+    /// Seed for the simulation RNG (0 picks a random one)
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Simulation config file (TOML or JSON)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Simulation name, used for saves, autosaves and stats files
+    #[arg(long, default_value = "Simulation")]
+    pub name: String,
+
+    /// World size as WIDTHxHEIGHT, e.g. 3200x1800
+    #[arg(long, value_name = "WxH", value_parser = parse_world_size)]
+    pub world_size: Option<(f32, f32)>,
+
+    /// Run without a window
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Number of fixed steps to run in headless mode
+    #[arg(long, default_value_t = HEADLESS_STEPS)]
+    pub steps: u64,
+
+    /// Snapshot to load instead of starting a new simulation
+    #[arg(long, value_name = "FILE")]
+    pub load: Option<PathBuf>,
+
+    /// Map file to start the simulation on
+    #[arg(long, value_name = "FILE", conflicts_with = "load")]
+    pub map: Option<PathBuf>,
+
+    /// Directory for CSV stats output
+    #[arg(long, value_name = "DIR")]
+    pub stats_dir: Option<PathBuf>,
+
+    /// Font used by the windowed front-end
+    #[arg(long, value_name = "FILE", default_value = "firacode.ttf")]
+    pub font: String,
+}
+
+impl Cli {
+    pub fn overrides(&self) -> ConfigOverrides {
+        return ConfigOverrides {
+            seed: self.seed,
+            world_size: self.world_size,
+        };
+    }
+}
+
+fn parse_world_size(value: &str) -> Result<(f32, f32), String> {
+    let (w, h) = value.split_once(['x', 'X']).ok_or_else(|| format!("expected WIDTHxHEIGHT, got '{}'", value))?;
+    let w: f32 = w.trim().parse().map_err(|_| format!("invalid world width '{}'", w))?;
+    let h: f32 = h.trim().parse().map_err(|_| format!("invalid world height '{}'", h))?;
+    if w <= 0.0 || h <= 0.0 {
+        return Err(format!("world size must be positive, got '{}'", value));
+    }
+    return Ok((w, h));
+}
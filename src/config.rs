@@ -0,0 +1,496 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+use crate::consts::*;
+use crate::neuro::Activation;
+
+
+//?         [[[SIM_CONFIG]]]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    pub world_width: f32,
+    pub world_height: f32,
+    pub boundary: BoundaryMode,
+    pub agents_init_num: usize,
+    pub agent_min_num: usize,
+    pub agent_size_min: f32,
+    pub agent_size_max: f32,
+    pub agent_speed: f32,
+    pub agent_vision_range: f32,
+    pub agent_rotation: f32,
+    pub agent_analyze_interval: f32,
+    pub agent_eng_capacity: f32,
+    pub agent_metabolism: f32,
+    pub agent_brain_hidden: Vec<usize>,
+    pub agent_brain_hidden_activation: Activation,
+    pub agent_brain_output_activation: Activation,
+    pub agent_eat_rate: f32,
+    pub attack_damage: f32,
+    pub attack_efficiency: f32,
+    pub attack_cost: f32,
+    pub sources_init_num: usize,
+    pub sources_min_num: usize,
+    pub sources_max_num: usize,
+    pub source_size_min: f32,
+    pub source_size_max: f32,
+    pub source_eng_capacity: f32,
+    pub source_growth_rate: f32,
+    pub source_seed_interval: f32,
+    pub source_seed_range: f32,
+    pub source_seed_cost: f32,
+    pub source_seed_min_eng: f32,
+    pub nutrients: bool,
+    pub nutrient_cell_size: f32,
+    pub nutrient_max: f32,
+    pub nutrient_regen: f32,
+    pub repro_eng_threshold: f32,
+    pub repro_eng_cost: f32,
+    pub mutation_rate: f32,
+    pub mutation_strength: f32,
+    pub mate_min_eng: f32,
+    pub mate_eng_cost: f32,
+    pub mate_cooldown: f32,
+    pub mate_max_distance: f32,
+    pub asteroids_num: usize,
+    pub asteroid_size_min: f32,
+    pub asteroid_size_max: f32,
+    pub asteroid_speed: f32,
+    pub impact_damage: f32,
+    pub corpse_eng_ratio: f32,
+    pub corpse_decay_rate: f32,
+    pub autosave_interval: f32,
+    pub autosave_keep: usize,
+    pub stats_interval: f32,
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            world_width: WORLD_W,
+            world_height: WORLD_H,
+            boundary: BoundaryMode::Wrap,
+            agents_init_num: AGENTS_NUM,
+            agent_min_num: AGENTS_NUM_MIN,
+            agent_size_min: AGENT_SIZE_MIN as f32,
+            agent_size_max: AGENT_SIZE_MAX as f32,
+            agent_speed: AGENT_SPEED,
+            agent_rotation: AGENT_ROTATION,
+            agent_vision_range: AGENT_VISION_RANGE,
+            agent_analyze_interval: AGENT_ANALYZE_INTERVAL,
+            agent_eng_capacity: AGENT_ENG_CAPACITY,
+            agent_metabolism: AGENT_METABOLISM,
+            agent_brain_hidden: AGENT_BRAIN_HIDDEN.to_vec(),
+            agent_brain_hidden_activation: Activation::Tanh,
+            agent_brain_output_activation: Activation::Tanh,
+            agent_eat_rate: AGENT_EAT_RATE,
+            attack_damage: ATTACK_DAMAGE,
+            attack_efficiency: ATTACK_EFFICIENCY,
+            attack_cost: ATTACK_COST,
+            sources_init_num: SOURCES_NUM,
+            sources_min_num: SOURCES_NUM_MIN,
+            sources_max_num: SOURCES_NUM_MAX,
+            source_size_min: SOURCE_SIZE_MIN,
+            source_size_max: SOURCE_SIZE_MAX,
+            source_eng_capacity: SOURCE_ENG_CAPACITY,
+            source_growth_rate: SOURCE_GROWTH_RATE,
+            source_seed_interval: SOURCE_SEED_INTERVAL,
+            source_seed_range: SOURCE_SEED_RANGE,
+            source_seed_cost: SOURCE_SEED_COST,
+            source_seed_min_eng: SOURCE_SEED_MIN_ENG,
+            nutrients: false,
+            nutrient_cell_size: NUTRIENT_CELL_SIZE,
+            nutrient_max: NUTRIENT_MAX,
+            nutrient_regen: NUTRIENT_REGEN,
+            repro_eng_threshold: REPRO_ENG_THRESHOLD,
+            repro_eng_cost: REPRO_ENG_COST,
+            mutation_rate: MUTATION_RATE,
+            mutation_strength: MUTATION_STRENGTH,
+            mate_min_eng: MATE_MIN_ENG,
+            mate_eng_cost: MATE_ENG_COST,
+            mate_cooldown: MATE_COOLDOWN,
+            mate_max_distance: MATE_MAX_DISTANCE,
+            asteroids_num: ASTER_NUM,
+            asteroid_size_min: ASTER_SIZE_MIN as f32,
+            asteroid_size_max: ASTER_SIZE_MAX as f32,
+            asteroid_speed: ASTER_SPEED,
+            impact_damage: IMPACT_DAMAGE,
+            corpse_eng_ratio: CORPSE_ENG_RATIO,
+            corpse_decay_rate: CORPSE_DECAY_RATE,
+            autosave_interval: AUTOSAVE_INTERVAL,
+            autosave_keep: AUTOSAVE_KEEP,
+            stats_interval: STATS_INTERVAL,
+            seed: 0,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn new(
+        agents_num: usize,
+        agents_min_num: usize,
+        agent_speed: f32,
+        agent_turn: f32,
+        vision_range: f32,
+        sources_num: usize,
+        sources_min_num: usize,
+    ) -> Self {
+        Self {
+            agents_init_num: agents_num,
+            agent_min_num: agents_min_num,
+            agent_speed: agent_speed,
+            agent_rotation: agent_turn,
+            agent_vision_range: vision_range,
+            sources_init_num: sources_num,
+            sources_min_num: sources_min_num,
+            ..Default::default()
+        }
+    }
+
+    //  TOML for *.toml files, JSON for anything else
+    pub fn load(path: &Path) -> Result<SimConfig, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let is_toml = path.extension().is_some_and(|ext| ext == "toml");
+        let config = match is_toml {
+            true => SimConfig::from_toml(&text),
+            false => SimConfig::from_json(&text),
+        };
+        let config = config.map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        return Ok(config);
+    }
+
+    pub fn from_toml(text: &str) -> Result<SimConfig, String> {
+        let deserializer = toml::Deserializer::new(text);
+        return serde_path_to_error::deserialize(deserializer).map_err(|e| describe_error(e.path(), e.inner()));
+    }
+
+    pub fn from_json(text: &str) -> Result<SimConfig, String> {
+        let mut deserializer = serde_json::Deserializer::from_str(text);
+        return serde_path_to_error::deserialize(&mut deserializer).map_err(|e| describe_error(e.path(), e.inner()));
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut check = ConfigCheck::new();
+        check.positive("world_width", self.world_width);
+        check.positive("world_height", self.world_height);
+        //  sizes are floored to whole units, anything below 1 rounds down to nothing
+        check.at_least("agent_size_min", self.agent_size_min, 1.0);
+        check.less("agent_size_min", self.agent_size_min, "agent_size_max", self.agent_size_max);
+        check.positive("agent_speed", self.agent_speed);
+        check.positive("agent_vision_range", self.agent_vision_range);
+        check.positive("agent_rotation", self.agent_rotation);
+        check.positive("agent_analyze_interval", self.agent_analyze_interval);
+        check.positive("agent_eng_capacity", self.agent_eng_capacity);
+        check.non_negative("agent_metabolism", self.agent_metabolism);
+        if self.agent_brain_hidden.contains(&0) {
+            check.fail("agent_brain_hidden", "hidden layers can't be empty");
+        }
+        check.non_negative("agent_eat_rate", self.agent_eat_rate);
+        check.non_negative("attack_damage", self.attack_damage);
+        check.fraction("attack_efficiency", self.attack_efficiency);
+        check.non_negative("attack_cost", self.attack_cost);
+        check.not_greater("sources_min_num", self.sources_min_num as f32, "sources_max_num", self.sources_max_num as f32);
+        check.at_least("source_size_min", self.source_size_min, 1.0);
+        check.less("source_size_min", self.source_size_min, "source_size_max", self.source_size_max);
+        check.positive("source_eng_capacity", self.source_eng_capacity);
+        check.non_negative("source_growth_rate", self.source_growth_rate);
+        check.positive("source_seed_interval", self.source_seed_interval);
+        check.non_negative("source_seed_range", self.source_seed_range);
+        check.fraction("source_seed_cost", self.source_seed_cost);
+        check.fraction("source_seed_min_eng", self.source_seed_min_eng);
+        check.positive("nutrient_cell_size", self.nutrient_cell_size);
+        check.non_negative("nutrient_max", self.nutrient_max);
+        check.non_negative("nutrient_regen", self.nutrient_regen);
+        check.fraction("repro_eng_threshold", self.repro_eng_threshold);
+        check.fraction("repro_eng_cost", self.repro_eng_cost);
+        check.not_greater("repro_eng_cost", self.repro_eng_cost, "repro_eng_threshold", self.repro_eng_threshold);
+        check.fraction("mutation_rate", self.mutation_rate);
+        check.non_negative("mutation_strength", self.mutation_strength);
+        check.fraction("mate_min_eng", self.mate_min_eng);
+        check.fraction("mate_eng_cost", self.mate_eng_cost);
+        check.not_greater("mate_eng_cost", self.mate_eng_cost, "mate_min_eng", self.mate_min_eng);
+        check.non_negative("mate_cooldown", self.mate_cooldown);
+        check.non_negative("mate_max_distance", self.mate_max_distance);
+        check.at_least("asteroid_size_min", self.asteroid_size_min, 1.0);
+        check.less("asteroid_size_min", self.asteroid_size_min, "asteroid_size_max", self.asteroid_size_max);
+        check.non_negative("asteroid_speed", self.asteroid_speed);
+        check.non_negative("impact_damage", self.impact_damage);
+        check.fraction("corpse_eng_ratio", self.corpse_eng_ratio);
+        check.non_negative("corpse_decay_rate", self.corpse_decay_rate);
+        check.non_negative("autosave_interval", self.autosave_interval);
+        if self.autosave_keep == 0 {
+            check.fail("autosave_keep", "must keep at least one autosave");
+        }
+        check.positive("stats_interval", self.stats_interval);
+        return check.finish();
+    }
+}
+
+//  keys that only take effect when a new simulation is started
+pub const RESTART_KEYS: [&str; 13] = [
+    "world_width",
+    "world_height",
+    "boundary",
+    "agents_init_num",
+    "sources_init_num",
+    "asteroids_num",
+    "agent_brain_hidden",
+    "agent_brain_hidden_activation",
+    "agent_brain_output_activation",
+    "agent_analyze_interval",
+    "nutrients",
+    "nutrient_cell_size",
+    "seed",
+];
+
+impl SimConfig {
+    pub fn changed_keys(&self, other: &SimConfig) -> Vec<String> {
+        let mut keys: Vec<String> = vec![];
+        let (old, new) = match (serde_json::to_value(self), serde_json::to_value(other)) {
+            (Ok(serde_json::Value::Object(old)), Ok(serde_json::Value::Object(new))) => (old, new),
+            _ => return keys,
+        };
+        for (key, value) in new.iter() {
+            if old.get(key) != Some(value) {
+                keys.push(key.clone());
+            }
+        }
+        return keys;
+    }
+
+    //  copy of `other` that keeps this config's values for every restart-only key
+    pub fn with_live_keys(&self, other: &SimConfig) -> SimConfig {
+        let mut live = other.clone();
+        live.world_width = self.world_width;
+        live.world_height = self.world_height;
+        live.boundary = self.boundary;
+        live.agents_init_num = self.agents_init_num;
+        live.sources_init_num = self.sources_init_num;
+        live.asteroids_num = self.asteroids_num;
+        live.agent_brain_hidden = self.agent_brain_hidden.clone();
+        live.agent_brain_hidden_activation = self.agent_brain_hidden_activation;
+        live.agent_brain_output_activation = self.agent_brain_output_activation;
+        live.agent_analyze_interval = self.agent_analyze_interval;
+        live.nutrients = self.nutrients;
+        live.nutrient_cell_size = self.nutrient_cell_size;
+        live.seed = self.seed;
+        return live;
+    }
+}
+
+//?         [[[BOUNDARY_MODE]]]
+//  what happens to anything that leaves the world rectangle
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryMode {
+    Wrap,
+    Walls,
+    Bounce,
+}
+
+impl BoundaryMode {
+    pub const ALL: [BoundaryMode; 3] = [BoundaryMode::Wrap, BoundaryMode::Walls, BoundaryMode::Bounce];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoundaryMode::Wrap => "Wrap",
+            BoundaryMode::Walls => "Walls",
+            BoundaryMode::Bounce => "Bounce",
+        }
+    }
+}
+
+//?         [[[CONFIG_PRESET]]]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConfigPreset {
+    Default,
+    SmallArena,
+    LargeArena,
+    Crowded,
+    Calm,
+}
+
+impl ConfigPreset {
+    pub const ALL: [ConfigPreset; 5] = [
+        ConfigPreset::Default,
+        ConfigPreset::SmallArena,
+        ConfigPreset::LargeArena,
+        ConfigPreset::Crowded,
+        ConfigPreset::Calm,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConfigPreset::Default => "Default",
+            ConfigPreset::SmallArena => "Small Arena",
+            ConfigPreset::LargeArena => "Large Arena",
+            ConfigPreset::Crowded => "Crowded",
+            ConfigPreset::Calm => "Calm",
+        }
+    }
+
+    pub fn config(&self) -> SimConfig {
+        let mut config = SimConfig::default();
+        match self {
+            ConfigPreset::Default => {},
+            ConfigPreset::SmallArena => {
+                config.world_width = WORLD_W / 2.0;
+                config.world_height = WORLD_H / 2.0;
+                config.sources_init_num = SOURCES_NUM / 4;
+                config.sources_min_num = SOURCES_NUM_MIN / 4;
+                config.sources_max_num = SOURCES_NUM_MAX / 4;
+                config.asteroids_num = ASTER_NUM / 4;
+            },
+            ConfigPreset::LargeArena => {
+                config.world_width = WORLD_W * 2.0;
+                config.world_height = WORLD_H * 2.0;
+                config.sources_init_num = SOURCES_NUM * 4;
+                config.sources_min_num = SOURCES_NUM_MIN * 4;
+                config.sources_max_num = SOURCES_NUM_MAX * 4;
+                config.asteroids_num = ASTER_NUM * 4;
+            },
+            ConfigPreset::Crowded => {
+                config.agents_init_num = 100;
+                config.agent_min_num = 50;
+                config.sources_max_num = SOURCES_NUM_MAX * 2;
+            },
+            ConfigPreset::Calm => {
+                config.asteroids_num = 0;
+                config.nutrients = true;
+            },
+        }
+        return config;
+    }
+}
+
+//?         [[[CONFIG_OVERRIDES]]]
+//  command-line values that win over whatever the config file says
+#[derive(Clone, Default)]
+pub struct ConfigOverrides {
+    pub seed: Option<u64>,
+    pub world_size: Option<(f32, f32)>,
+}
+
+impl ConfigOverrides {
+    pub fn apply(&self, config: &mut SimConfig) {
+        if let Some(seed) = self.seed {
+            config.seed = seed;
+        }
+        if let Some((w, h)) = self.world_size {
+            config.world_width = w;
+            config.world_height = h;
+        }
+    }
+}
+
+//?         [[[CONFIG_WATCHER]]]
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    overrides: ConfigOverrides,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path, overrides: ConfigOverrides) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            overrides,
+            modified: ConfigWatcher::modified_time(path),
+            last_poll: Instant::now(),
+        }
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        return fs::metadata(path).and_then(|meta| meta.modified()).ok();
+    }
+
+    //  Some(..) only when the file changed since the last poll
+    pub fn poll(&mut self) -> Option<Result<SimConfig, String>> {
+        if self.last_poll.elapsed().as_secs_f32() < CONFIG_POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+        let modified = ConfigWatcher::modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        let result = SimConfig::load(&self.path).and_then(|mut config| {
+            self.overrides.apply(&mut config);
+            config.validate()?;
+            return Ok(config);
+        });
+        return Some(result);
+    }
+}
+
+fn describe_error(path: &serde_path_to_error::Path, error: &dyn std::fmt::Display) -> String {
+    let key = path.to_string();
+    if key.is_empty() || key == "." {
+        return error.to_string();
+    }
+    return format!("`{}`: {}", key, error);
+}
+
+//  collects every invalid key instead of stopping at the first one
+struct ConfigCheck {
+    errors: Vec<String>,
+}
+
+impl ConfigCheck {
+    fn new() -> Self {
+        Self { errors: vec![] }
+    }
+
+    fn fail(&mut self, key: &str, reason: &str) {
+        self.errors.push(format!("`{}`: {}", key, reason));
+    }
+
+    fn positive(&mut self, key: &str, value: f32) {
+        if value.is_nan() || value <= 0.0 {
+            self.fail(key, &format!("must be greater than 0 (got {})", value));
+        }
+    }
+
+    fn non_negative(&mut self, key: &str, value: f32) {
+        if value.is_nan() || value < 0.0 {
+            self.fail(key, &format!("can't be negative (got {})", value));
+        }
+    }
+
+    fn at_least(&mut self, key: &str, value: f32, min: f32) {
+        if value.is_nan() || value < min {
+            self.fail(key, &format!("must be at least {} (got {})", min, value));
+        }
+    }
+
+    fn fraction(&mut self, key: &str, value: f32) {
+        if !(0.0..=1.0).contains(&value) {
+            self.fail(key, &format!("must be between 0 and 1 (got {})", value));
+        }
+    }
+
+    fn less(&mut self, key: &str, value: f32, max_key: &str, max: f32) {
+        if value.is_nan() || max.is_nan() || value >= max {
+            self.fail(key, &format!("must be less than `{}` ({} >= {})", max_key, value, max));
+        }
+    }
+
+    fn not_greater(&mut self, key: &str, value: f32, max_key: &str, max: f32) {
+        if value.is_nan() || max.is_nan() || value > max {
+            self.fail(key, &format!("can't be greater than `{}` ({} > {})", max_key, value, max));
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        return Err(self.errors.join("; "));
+    }
+}
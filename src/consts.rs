@@ -4,6 +4,9 @@ pub const SCREEN_WIDTH: f32         = 1600.0;
 pub const SCREEN_HEIGHT: f32        = 900.0;
 pub const WORLD_W: f32              = 3200.0;
 pub const WORLD_H: f32              = 1800.0;
+pub const WORLD_SIZE_MIN: f32       = 400.0;
+pub const WORLD_SIZE_MAX: f32       = 20000.0;
+pub const WALL_THICKNESS: f32       = 50.0;
 
 pub const AGENTS_NUM: usize         = 0;
 pub const AGENTS_NUM_MIN: usize     = 0;
@@ -12,12 +15,67 @@ pub const AGENT_SIZE_MAX: i32       = 10;
 pub const AGENT_SPEED: f32          = 40.0;
 pub const AGENT_VISION_RANGE: f32   = 250.0;
 pub const AGENT_ROTATION: f32       = 2.0;
+pub const AGENT_ANALYZE_INTERVAL: f32 = 0.3;
+pub const AGENT_ENG_CAPACITY: f32   = 10.0;
+pub const AGENT_METABOLISM: f32     = 1.0;
+pub const AGENT_BRAIN_INPUTS: usize = 5;
+pub const AGENT_BRAIN_HIDDEN: [usize; 2] = [8, 6];
+pub const AGENT_BRAIN_OUTPUTS: usize = 3;
+
+pub const MUTATION_RATE: f32        = 0.1;
+pub const MUTATION_STRENGTH: f32    = 0.2;
+pub const REPRO_ENG_THRESHOLD: f32  = 0.8;
+pub const REPRO_ENG_COST: f32       = 0.5;
+pub const MATE_MIN_ENG: f32         = 0.6;
+pub const MATE_ENG_COST: f32        = 0.3;
+pub const MATE_COOLDOWN: f32        = 10.0;
+pub const MATE_MAX_DISTANCE: f32    = 0.25;
+
+pub const AGENT_EAT_RATE: f32       = 50.0;
+pub const ATTACK_DAMAGE: f32        = 100.0;
+pub const ATTACK_EFFICIENCY: f32    = 0.5;
+pub const ATTACK_COST: f32          = 2.0;
+
+pub const SOURCES_NUM: usize        = 64;
+pub const SOURCES_NUM_MIN: usize    = 32;
+pub const SOURCES_NUM_MAX: usize    = 256;
+pub const SOURCE_SIZE_MIN: f32      = 5.0;
+pub const SOURCE_SIZE_MAX: f32      = 10.0;
+pub const SOURCE_ENG_CAPACITY: f32  = 10.0;
+pub const SOURCE_SEED_MIN_ENG: f32  = 0.9;
+pub const SOURCE_GROWTH_RATE: f32   = 5.0;
+pub const SOURCE_SEED_INTERVAL: f32 = 15.0;
+pub const SOURCE_SEED_RANGE: f32    = 80.0;
+pub const SOURCE_SEED_COST: f32     = 0.25;
+
+pub const CORPSE_ENG_RATIO: f32     = 0.5;
+pub const CORPSE_DECAY_RATE: f32    = 5.0;
+
+pub const NUTRIENT_CELL_SIZE: f32   = 100.0;
+pub const NUTRIENT_MAX: f32         = 1000.0;
+pub const NUTRIENT_REGEN: f32       = 2.0;
 
 pub const ASTER_SIZE_MIN: u32       = 8;
 pub const ASTER_SIZE_MAX: u32       = 18;
 pub const ASTER_NUM: usize          = 4098;
 pub const ASTER_SPEED: f32          = 100.0; 
+pub const IMPACT_DAMAGE: f32        = 0.0001;
+
+pub const SAVES_DIR: &str           = "saves";
+pub const MAPS_DIR: &str            = "maps";
+pub const SPAWN_TRIES: usize        = 16;
+pub const ERASE_RANGE: f32          = 8.0;
+pub const SPAWN_ZONE_MIN: f32       = 10.0;
+pub const AUTOSAVE_INTERVAL: f32    = 300.0;
+pub const AUTOSAVE_KEEP: usize      = 5;
+pub const STATS_INTERVAL: f32       = 10.0;
+pub const CONFIG_POLL_INTERVAL: f32 = 1.0;
 
 pub const FIX_DT: f32               = 1.0/30.0;
+pub const MAX_STEPS_PER_FRAME: u32  = 8;
+pub const MAX_SPEED_BUDGET: f32     = 1.0/30.0;
+pub const HEADLESS_STEPS: u64       = 10000;
+pub const HEADLESS_REPORT_INTERVAL: u64 = 1000;
+pub const LERP_MAX_JUMP: f32        = 50.0;
 pub const ZOOM_RATE: f32            = 1.0/800.0;
 pub const SCREEN_RATIO: f32         = SCREEN_WIDTH/SCREEN_HEIGHT;
\ No newline at end of file
@@ -0,0 +1,163 @@
+use macroquad::prelude::*;
+use crate::consts::*;
+use crate::map::SpawnZone;
+use crate::obstacle::ObstacleKind;
+use crate::sim::Simulation;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EditTool {
+    Polyline,
+    Convex,
+    Source,
+    Asteroid,
+    SpawnZone,
+    Erase,
+}
+
+impl EditTool {
+    pub const ALL: [EditTool; 6] = [
+        EditTool::Polyline,
+        EditTool::Convex,
+        EditTool::Source,
+        EditTool::Asteroid,
+        EditTool::SpawnZone,
+        EditTool::Erase,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EditTool::Polyline => "Wall",
+            EditTool::Convex => "Block",
+            EditTool::Source => "Source",
+            EditTool::Asteroid => "Asteroid",
+            EditTool::SpawnZone => "Spawn Zone",
+            EditTool::Erase => "Erase",
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            EditTool::Polyline => "click to add points, right click or Enter to finish, Backspace undoes a point",
+            EditTool::Convex => "click to add corners, right click or Enter to finish, Backspace undoes a corner",
+            EditTool::Source => "click to place a food source",
+            EditTool::Asteroid => "click to place an asteroid",
+            EditTool::SpawnZone => "drag to paint a region new agents spawn in",
+            EditTool::Erase => "click on anything placed to remove it",
+        }
+    }
+}
+
+//  mouse driven layout editing, works in world coordinates on the live simulation
+pub struct MapEditor {
+    pub tool: EditTool,
+    pub map_name: String,
+    points: Vec<Vec2>,
+    drag_start: Option<Vec2>,
+}
+
+impl MapEditor {
+    pub fn new() -> Self {
+        Self {
+            tool: EditTool::Polyline,
+            map_name: String::new(),
+            points: vec![],
+            drag_start: None,
+        }
+    }
+
+    pub fn set_tool(&mut self, tool: EditTool) {
+        if tool != self.tool {
+            self.cancel();
+            self.tool = tool;
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.points.clear();
+        self.drag_start = None;
+    }
+
+    pub fn input(&mut self, sim: &mut Simulation, cursor: Vec2, keyboard: bool) {
+        if keyboard && is_key_pressed(KeyCode::Escape) {
+            self.cancel();
+        }
+        match self.tool {
+            EditTool::Polyline | EditTool::Convex => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    self.points.push(cursor);
+                }
+                if keyboard && is_key_pressed(KeyCode::Backspace) {
+                    self.points.pop();
+                }
+                let finish = is_mouse_button_pressed(MouseButton::Right) || (keyboard && is_key_pressed(KeyCode::Enter));
+                if finish {
+                    self.finish_shape(sim);
+                }
+            },
+            EditTool::Source => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    sim.add_source_at(cursor);
+                }
+            },
+            EditTool::Asteroid => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    sim.add_asteroid_at(cursor);
+                }
+            },
+            EditTool::SpawnZone => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    self.drag_start = Some(cursor);
+                }
+                if is_mouse_button_released(MouseButton::Left) {
+                    if let Some(start) = self.drag_start.take() {
+                        sim.add_spawn_zone(SpawnZone::from_corners(start, cursor));
+                    }
+                }
+            },
+            EditTool::Erase => {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    sim.erase_at(cursor);
+                }
+            },
+        }
+    }
+
+    fn finish_shape(&mut self, sim: &mut Simulation) {
+        let kind = match self.tool {
+            EditTool::Convex => ObstacleKind::Convex,
+            _ => ObstacleKind::Polyline,
+        };
+        if sim.add_obstacle(kind, self.points.clone()).is_none() {
+            eprintln!("obstacle needs at least {} points", if kind == ObstacleKind::Convex { 3 } else { 2 });
+        }
+        self.points.clear();
+    }
+
+    pub fn draw(&self, sim: &Simulation, cursor: Vec2) {
+        for zone in sim.layout.spawn_zones.iter() {
+            zone.draw();
+        }
+        for placement in sim.layout.sources.iter().chain(sim.layout.asteroids.iter()) {
+            draw_circle_lines(placement.pos[0], placement.pos[1], ERASE_RANGE, 1.0, SKYBLUE);
+        }
+        if let Some(start) = self.drag_start {
+            SpawnZone::from_corners(start, cursor).draw();
+        }
+        if !self.points.is_empty() {
+            for i in 1..self.points.len() {
+                let (a, b) = (self.points[i - 1], self.points[i]);
+                draw_line(a.x, a.y, b.x, b.y, 2.0, ORANGE);
+            }
+            let last = self.points[self.points.len() - 1];
+            draw_line(last.x, last.y, cursor.x, cursor.y, 1.0, ORANGE);
+            if self.tool == EditTool::Convex {
+                let first = self.points[0];
+                draw_line(cursor.x, cursor.y, first.x, first.y, 1.0, ORANGE);
+            }
+            for p in self.points.iter() {
+                draw_circle(p.x, p.y, 3.0, ORANGE);
+            }
+        }
+        draw_circle_lines(cursor.x, cursor.y, 4.0, 1.0, ORANGE);
+    }
+}
@@ -1,22 +1,23 @@
 #![allow(unused)]
-use std::collections::hash_map::{Iter, IterMut};
-use std::collections::HashMap;
+use std::collections::btree_map::{Iter, IterMut};
+use std::collections::BTreeMap;
 //use std::f32::consts::PI;
 
 use macroquad::{color, prelude::*};
 use nalgebra::{Point2};
+use crate::config::SimConfig;
 use crate::consts::*;
 use crate::kinetic::make_isometry;
 use crate::util::*;
 use crate::world::*;
-use ::rand::{thread_rng, Rng};
+use ::rand::{Rng, rngs::StdRng};
 use rapier2d::geometry::*;
 use rapier2d::prelude::RigidBodyHandle;
 
 pub trait DynamicElement {
     //fn create() -> Self;
-    fn draw(&self, font: Font);
-    fn update(&mut self, dt: f32, physics: &mut World);
+    fn draw(&self, font: Font, alpha: f32, offset: Vec2);
+    fn update(&mut self, dt: f32, physics: &mut World, bounds: &WorldBounds);
 }
 
 
@@ -24,6 +25,8 @@ pub struct Asteroid {
     pub key: u64,
     pub pos: Vec2,
     pub rot: f32,
+    prev_pos: Vec2,
+    prev_rot: f32,
     pub vel: f32,
     pub ang_vel: f32,
     pub size: f32,
@@ -33,36 +36,49 @@ pub struct Asteroid {
     pub shape: ConvexPolygon,
     pub physics_handle: Option<RigidBodyHandle>,
     pub kin_eng: f32,
+    pub lin_vel: Vec2,
+    pub mass: f32,
+    pub contacts: Vec<u64>,
 }
 
 impl Asteroid {
-    pub fn new() -> Self {
-        let size = rand::gen_range(ASTER_SIZE_MIN, ASTER_SIZE_MAX);
+    pub fn new(config: &SimConfig, rng: &mut StdRng) -> Self {
+        let size = rng.gen_range(config.asteroid_size_min..config.asteroid_size_max).floor();
         //let n = size / 4;
-        let n = rand::gen_range(8, 16);
-        let points = map_polygon(n as usize, size as f32, 0.5);
+        let n = rng.gen_range(8..16);
+        let points = map_polygon(n as usize, size, 0.5, rng);
         let points2 = vec2_to_point2_collection(&points);
+        let lin_vel = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * config.asteroid_speed;
+        let pos = random_position(config.world_width, config.world_height, rng);
+        let rot = random_rotation(rng);
         Self {
-            key: thread_rng().gen::<u64>(),
-            pos: random_position(WORLD_W, WORLD_H),
-            rot: random_rotation(),
-            vel: rand::gen_range(0.0, 1.0) * ASTER_SPEED,
-            ang_vel: rand::gen_range(-1.0, 1.0),
-            size: (size as f32),
-            color: random_color(),
+            key: rng.gen::<u64>(),
+            pos,
+            rot,
+            prev_pos: pos,
+            prev_rot: rot,
+            vel: rng.gen_range(0.0..1.0) * config.asteroid_speed,
+            ang_vel: rng.gen_range(-1.0..1.0),
+            size,
+            color: random_color(rng),
             points: points,
             points2: points2.clone(),
             shape: ConvexPolygon::from_convex_polyline(points2).unwrap(),
             physics_handle: None,
             kin_eng: 0.0,
+            lin_vel,
+            mass: 0.0,
+            contacts: vec![],
         }        
     }
 }
 
 impl DynamicElement for Asteroid {
-    fn draw(&self, font: Font) {
-        let x0 = self.pos.x;
-        let y0 = self.pos.y;
+    fn draw(&self, font: Font, alpha: f32, offset: Vec2) {
+        let (pos, rot) = lerp_pose(self.prev_pos, self.pos, self.prev_rot, self.rot, alpha);
+        let pos = pos + offset;
+        let x0 = pos.x;
+        let y0 = pos.y;
         let l = self.points.len();
         for i in 1..=l {
             let mut v1: &Vec2;
@@ -75,8 +91,8 @@ impl DynamicElement for Asteroid {
                 v2 = self.points.get(i).unwrap();    
             }
             //let v1n = v1.normalize_or_zero();
-            let v1r = v1.rotate(Vec2::from_angle(self.rot));
-            let v2r = v2.rotate(Vec2::from_angle(self.rot));
+            let v1r = v1.rotate(Vec2::from_angle(rot));
+            let v2r = v2.rotate(Vec2::from_angle(rot));
             
             draw_line(v1r.x+x0, v1r.y+y0, v2r.x+x0, v2r.y+y0, 4.0, self.color);
         }
@@ -91,40 +107,21 @@ impl DynamicElement for Asteroid {
         draw_text_ex(&kin_eng_info, x0-txt_center.x, y0-txt_center.y, text_cfg);
         //draw_text(kin_eng_info, x0-18.0, y0, 16.0, WHITE);
     }
-    fn update(&mut self, dt: f32, physics: &mut World) {
+    fn update(&mut self, dt: f32, physics: &mut World, bounds: &WorldBounds) {
         match self.physics_handle {
             Some(handle) => {
                 let physics_data = physics.get_physics_data(handle);
+                self.prev_pos = self.pos;
+                self.prev_rot = self.rot;
                 self.pos = physics_data.position;
                 self.rot = physics_data.rotation;
                 self.kin_eng = physics_data.kin_eng.unwrap();
-                match physics.rigid_bodies.get_mut(handle) {
-                    Some(body) => {
-                        let dir = Vec2::from_angle(self.rot);
-                        let v = dir * self.vel;
-                        //body.set_linvel([v.x, v.y].into(), true);
-                        //body.set_angvel(self.ang_vel, true);
-                        let mut raw_pos = matric_to_vec2(body.position().translation);
-                        let mut out_of_edge = false;
-                        if raw_pos.x < 0.0 {
-                            raw_pos.x = WORLD_W-5.0;
-                            out_of_edge = true;
-                        } else if raw_pos.x > WORLD_W {
-                            raw_pos.x = 5.0;
-                            out_of_edge = true;
-                        }
-                        if raw_pos.y < 0.0 {
-                            raw_pos.y = WORLD_H - 5.0;
-                            out_of_edge = true;
-                        } else if raw_pos.y > WORLD_H {
-                            raw_pos.y = 5.0;
-                            out_of_edge = true;
-                        }
-                        if out_of_edge {
-                            body.set_position(make_isometry(raw_pos.x, raw_pos.y, self.rot), true);
-                        }
-                    },
-                    None => {}
+                self.lin_vel = physics_data.lin_vel;
+                self.ang_vel = physics_data.ang_vel;
+                self.mass = physics_data.mass;
+                self.contacts = physics.get_contacts(self.key);
+                if let Some((pos, vel)) = bounds.confine(self.pos, self.lin_vel) {
+                    physics.set_body_state(handle, pos, self.rot, vel, self.ang_vel);
                 }
             },
             None => {},
@@ -135,19 +132,19 @@ impl DynamicElement for Asteroid {
 
 
 pub struct DynamicCollector {
-    pub elements: HashMap<u64, Asteroid>,
+    pub elements: BTreeMap<u64, Asteroid>,
 }
 
 impl DynamicCollector {
     pub fn new() -> Self {
         Self {
-            elements: HashMap::new(),
+            elements: BTreeMap::new(),
         }
     }
 
-    pub fn add_many_elements(&mut self, elements_num: usize, physics_world: &mut World) {
+    pub fn add_many_elements(&mut self, elements_num: usize, physics_world: &mut World, config: &SimConfig, rng: &mut StdRng) {
         for _ in 0..elements_num {
-            let element = Asteroid::new();
+            let element = Asteroid::new(config, rng);
             _ = self.add_element(element, physics_world);
         }
     }
@@ -155,8 +152,10 @@ impl DynamicCollector {
     pub fn add_element(&mut self, mut element: Asteroid, physics_world: &mut World) -> u64 {
         let key = element.key;
         //let handle = physics_world.add_poly_body(key,&element.pos, element.points2.clone());
-        let handle = physics_world.add_poly_body(key,&element.pos, element.points2.clone());
+        let handle = physics_world.add_poly_body(key,&element.pos, element.points2.clone(), element.lin_vel);
         element.physics_handle = Some(handle);
+        element.prev_pos = element.pos;
+        element.prev_rot = element.rot;
         self.elements.insert(key, element);
         return key;
     }
@@ -0,0 +1,130 @@
+#![allow(unused)]
+
+use macroquad::{color, prelude::*};
+use ::rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use crate::config::SimConfig;
+use crate::consts::*;
+use crate::neuro::*;
+use crate::snapshot::ColorDef;
+use crate::util::*;
+
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Genome {
+    pub size: f32,
+    pub vision_range: f32,
+    #[serde(with = "ColorDef")]
+    pub color: color::Color,
+    pub motor: bool,
+    pub turn_rate: f32,
+    pub speed: f32,
+    pub brain: Network,
+}
+
+impl Genome {
+    pub fn random(config: &SimConfig, rng: &mut StdRng) -> Self {
+        Self {
+            size: rng.gen_range(config.agent_size_min..config.agent_size_max).floor(),
+            vision_range: (rng.gen_range(0.5..1.5) * config.agent_vision_range).round(),
+            color: random_color(rng),
+            motor: rng.gen_bool(1.0),
+            turn_rate: rng.gen_range(0.5..1.5) * config.agent_rotation,
+            speed: rng.gen_range(0.5..1.5) * config.agent_speed,
+            brain: Genome::random_brain(config, rng),
+        }
+    }
+
+    fn random_brain(config: &SimConfig, rng: &mut StdRng) -> Network {
+        let mut layers: Vec<usize> = vec![AGENT_BRAIN_INPUTS];
+        layers.extend_from_slice(&config.agent_brain_hidden);
+        layers.push(AGENT_BRAIN_OUTPUTS);
+        return Network::new(&layers, config.agent_brain_hidden_activation, config.agent_brain_output_activation, rng);
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.size.is_nan() || self.size <= 0.0 {
+            return Err(format!("size {} must be positive", self.size));
+        }
+        self.brain.validate()?;
+        if self.brain.inputs_num() != AGENT_BRAIN_INPUTS || self.brain.outputs_num() != AGENT_BRAIN_OUTPUTS {
+            return Err(format!(
+                "brain has {} inputs and {} outputs, expected {} and {}",
+                self.brain.inputs_num(), self.brain.outputs_num(), AGENT_BRAIN_INPUTS, AGENT_BRAIN_OUTPUTS
+            ));
+        }
+        return Ok(());
+    }
+
+    pub fn mutate(&mut self, config: &SimConfig, rng: &mut StdRng) {
+        let rate = config.mutation_rate;
+        //  gen_range panics on an empty range
+        let strength = config.mutation_strength.max(f32::EPSILON);
+        if rng.gen_bool(rate as f64) {
+            let size = self.size * (1.0 + rng.gen_range(-strength..strength));
+            self.size = size.clamp(config.agent_size_min, config.agent_size_max);
+        }
+        if rng.gen_bool(rate as f64) {
+            let vision_range = self.vision_range * (1.0 + rng.gen_range(-strength..strength));
+            self.vision_range = vision_range.clamp(0.5 * config.agent_vision_range, 1.5 * config.agent_vision_range).round();
+        }
+        if rng.gen_bool(rate as f64) {
+            self.color.r = (self.color.r + rng.gen_range(-strength..strength)).clamp(0.0, 1.0);
+            self.color.g = (self.color.g + rng.gen_range(-strength..strength)).clamp(0.0, 1.0);
+            self.color.b = (self.color.b + rng.gen_range(-strength..strength)).clamp(0.0, 1.0);
+        }
+        if rng.gen_bool((rate * strength).min(1.0) as f64) {
+            self.motor = !self.motor;
+        }
+        if rng.gen_bool(rate as f64) {
+            let turn_rate = self.turn_rate * (1.0 + rng.gen_range(-strength..strength));
+            self.turn_rate = turn_rate.clamp(0.25 * config.agent_rotation, 2.0 * config.agent_rotation);
+        }
+        if rng.gen_bool(rate as f64) {
+            let speed = self.speed * (1.0 + rng.gen_range(-strength..strength));
+            self.speed = speed.clamp(0.25 * config.agent_speed, 2.0 * config.agent_speed);
+        }
+        self.brain.mutate(rate, strength, rng);
+    }
+
+    pub fn distance(&self, other: &Genome, config: &SimConfig) -> f32 {
+        let size_range = config.agent_size_max - config.agent_size_min;
+        let color_diff = (self.color.r - other.color.r).abs()
+            + (self.color.g - other.color.g).abs()
+            + (self.color.b - other.color.b).abs();
+        let mut weights_diff = 0.0;
+        let mut weights_num = 0;
+        for (layer, layer2) in self.brain.layers.iter().zip(other.brain.layers.iter()) {
+            for (w, w2) in layer.weights.iter().zip(layer2.weights.iter()) {
+                weights_diff += (w - w2).abs();
+                weights_num += 1;
+            }
+        }
+        let traits = [
+            (self.size - other.size).abs() / size_range,
+            (self.vision_range - other.vision_range).abs() / config.agent_vision_range,
+            color_diff / 3.0,
+            (self.turn_rate - other.turn_rate).abs() / config.agent_rotation,
+            (self.speed - other.speed).abs() / config.agent_speed,
+            weights_diff / (2.0 * weights_num.max(1) as f32),
+        ];
+        return traits.iter().sum::<f32>() / traits.len() as f32;
+    }
+
+    pub fn crossover(&self, other: &Genome, rng: &mut StdRng) -> Genome {
+        Genome {
+            size: if rng.gen_bool(0.5) { self.size } else { other.size },
+            vision_range: if rng.gen_bool(0.5) { self.vision_range } else { other.vision_range },
+            color: Color::new(
+                (self.color.r + other.color.r) / 2.0,
+                (self.color.g + other.color.g) / 2.0,
+                (self.color.b + other.color.b) / 2.0,
+                1.0,
+            ),
+            motor: if rng.gen_bool(0.5) { self.motor } else { other.motor },
+            turn_rate: if rng.gen_bool(0.5) { self.turn_rate } else { other.turn_rate },
+            speed: if rng.gen_bool(0.5) { self.speed } else { other.speed },
+            brain: self.brain.crossover(&other.brain, rng),
+        }
+    }
+}
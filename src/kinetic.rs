@@ -6,6 +6,7 @@ use nalgebra::*;
 use parry2d::query::contact;
 use parry2d::shape::*;
 use parry2d::{query::Contact};
+use crate::util::WorldBounds;
 //use rapier2d::prelude::*;
 
 //use crate::agent::Agent;
@@ -22,7 +23,10 @@ pub fn contact_circles(
     pos2: Vec2,
     rot2: f32,
     rad2: f32,
+    bounds: &WorldBounds,
 ) -> Option<Contact> {
+    //  second circle is taken at its image nearest to the first one
+    let pos2 = pos1 + bounds.delta(pos1, pos2);
     let v1 = glam::Vec2::new(pos1.x, pos1.y);
     let v2 = glam::Vec2::new(pos2.x, pos2.y);
     let pos1 = make_isometry(v1.x, v1.y, rot1);
@@ -33,6 +37,15 @@ pub fn contact_circles(
     return contact;
 }
 
+pub fn impact_energy(mass1: f32, vel1: Vec2, mass2: f32, vel2: Vec2) -> f32 {
+    if mass1 + mass2 <= 0.0 {
+        return 0.0;
+    }
+    let reduced_mass = (mass1 * mass2) / (mass1 + mass2);
+    let rel_vel = vel1 - vel2;
+    return 0.5 * reduced_mass * rel_vel.length_squared();
+}
+
 pub fn contact_mouse(mouse_pos: Vec2, target_pos: Vec2, target_rad: f32) -> bool {
     let v1 = glam::Vec2::new(mouse_pos.x, mouse_pos.y);
     let v2 = glam::Vec2::new(target_pos.x, target_pos.y);
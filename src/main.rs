@@ -1,6 +1,7 @@
 #![allow(unused)]
 
 mod sim;
+mod app;
 mod consts;
 mod util;
 mod agent;
@@ -13,9 +14,22 @@ mod world;
 mod source;
 mod camera;
 mod element;
+mod genome;
+mod nutrients;
+mod snapshot;
+mod config;
+mod stats;
+mod cli;
+mod obstacle;
+mod map;
+mod editor;
 
+use clap::Parser;
 use macroquad::prelude::*;
+use crate::cli::*;
+use crate::config::*;
 use crate::sim::*;
+use crate::app::*;
 use crate::consts::*;
 use crate::util::*;
 pub use crate::source::*;
@@ -31,25 +45,85 @@ fn app_configuration() -> Conf {
     }
 }
 
-#[macroquad::main(app_configuration)]
-async fn main() {
-    let cfg = SimConfig::default();
-    let font = load_ttf_font("firacode.ttf").await.expect("can't load font resource!");
-    let mut sim = Simulation::new(cfg, font.clone());
-    sim.init();
-    sim.autorun_new_sim();    
-    
-    loop {
-        sim.input();
-        sim.process_ui();
-        if sim.is_running() {
-            sim.update();
-            sim.draw();
+fn main() {
+    let cli = Cli::parse();
+    let mut cfg = match cli.config.as_ref() {
+        Some(path) => match SimConfig::load(path) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            },
+        },
+        None => SimConfig::default(),
+    };
+    cli.overrides().apply(&mut cfg);
+    if let Err(e) = cfg.validate() {
+        eprintln!("invalid config: {}", e);
+        std::process::exit(2);
+    }
+    if cli.headless {
+        run_headless(cfg, &cli);
+        return;
+    }
+    macroquad::Window::from_config(app_configuration(), run_app(cfg, cli));
+}
+
+fn start_sim(sim: &mut Simulation, cli: &Cli) {
+    sim.stats_dir = cli.stats_dir.clone();
+    match cli.load.as_ref() {
+        Some(path) => {
+            if let Err(e) = sim.load_sim(path) {
+                eprintln!("load failed: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => match cli.map.as_ref() {
+            Some(path) => {
+                sim.simulation_name = cli.name.to_owned();
+                if let Err(e) = sim.load_map(path) {
+                    eprintln!("map load failed: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => sim.reset_sim(Some(&cli.name)),
+        },
+    }
+}
+
+fn run_headless(cfg: SimConfig, cli: &Cli) {
+    let mut sim = Simulation::new(cfg);
+    start_sim(&mut sim, cli);
+    println!("running {} headless, seed {}", sim.simulation_name, sim.sim_state.seed);
+    let mut watcher = cli.config.as_ref().map(|path| ConfigWatcher::new(path, cli.overrides()));
+    let steps = cli.steps;
+    for step in 1..=steps {
+        if let Some(watcher) = watcher.as_mut() {
+            sim.reload_config(watcher);
         }
-        else {
-            sim.signals_check();
+        sim.step();
+        if step % HEADLESS_REPORT_INTERVAL == 0 || step == steps {
+            let state = &sim.sim_state;
+            println!(
+                "step {:>8}  time {:>10.1}s  agents {:>5}  sources {:>5}  corpses {:>5}",
+                step, state.sim_time, state.agents_num, state.sources_num, state.corpses_num
+            );
         }
-        sim.draw_ui();
+    }
+}
+
+async fn run_app(cfg: SimConfig, cli: Cli) {
+    let font = load_ttf_font(&cli.font).await.expect("can't load font resource!");
+    let mut app = App::new(cfg, font.clone());
+    app.config_watcher = cli.config.as_ref().map(|path| ConfigWatcher::new(path, cli.overrides()));
+    start_sim(&mut app.sim, &cli);
+
+    loop {
+        app.input();
+        app.process_ui();
+        app.update();
+        app.draw();
+        app.draw_ui();
         next_frame().await;
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+use ::rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use crate::config::BoundaryMode;
+use crate::consts::*;
+use crate::snapshot::ObstacleData;
+use crate::util::file_stem;
+
+pub const MAP_VERSION: u32 = 1;
+
+//?         [[[SPAWN_ZONE]]]
+//  axis aligned rectangle new agents are placed in
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct SpawnZone {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl SpawnZone {
+    pub fn from_corners(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min(b).to_array(),
+            max: a.max(b).to_array(),
+        }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        return Vec2::from_array(self.max) - Vec2::from_array(self.min);
+    }
+
+    pub fn contains(&self, pos: Vec2) -> bool {
+        return pos.x >= self.min[0] && pos.x <= self.max[0] && pos.y >= self.min[1] && pos.y <= self.max[1];
+    }
+
+    pub fn random_position(&self, rng: &mut StdRng) -> Vec2 {
+        let x = if self.max[0] > self.min[0] { rng.gen_range(self.min[0]..self.max[0]) } else { self.min[0] };
+        let y = if self.max[1] > self.min[1] { rng.gen_range(self.min[1]..self.max[1]) } else { self.min[1] };
+        return Vec2::new(x, y);
+    }
+
+    pub fn draw(&self) {
+        let size = self.size();
+        draw_rectangle(self.min[0], self.min[1], size.x, size.y, Color::new(0.0, 0.8, 0.2, 0.1));
+        draw_rectangle_lines(self.min[0], self.min[1], size.x, size.y, 2.0, GREEN);
+    }
+}
+
+//?         [[[PLACEMENT]]]
+//  source or asteroid put down in the editor, the live entity it spawns shares its key
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Placement {
+    pub key: u64,
+    pub pos: [f32; 2],
+}
+
+//?         [[[MAP_FILE]]]
+//  reusable layout: terrain, placed sources and asteroids and agent spawn zones
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MapFile {
+    pub version: u32,
+    pub world_width: f32,
+    pub world_height: f32,
+    pub boundary: BoundaryMode,
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleData>,
+    #[serde(default)]
+    pub sources: Vec<Placement>,
+    #[serde(default)]
+    pub asteroids: Vec<Placement>,
+    #[serde(default)]
+    pub spawn_zones: Vec<SpawnZone>,
+}
+
+impl MapFile {
+    pub fn new() -> Self {
+        Self {
+            version: MAP_VERSION,
+            world_width: WORLD_W,
+            world_height: WORLD_H,
+            boundary: BoundaryMode::Wrap,
+            obstacles: vec![],
+            sources: vec![],
+            asteroids: vec![],
+            spawn_zones: vec![],
+        }
+    }
+
+    //  layout for snapshots saved before layouts were
+    pub fn from_obstacles(obstacles: Vec<ObstacleData>) -> Self {
+        let mut map = MapFile::new();
+        map.obstacles = obstacles;
+        return map;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("can't serialize map: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("can't write {}: {}", path.display(), e))?;
+        return Ok(());
+    }
+
+    pub fn load(path: &Path) -> Result<MapFile, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let map: MapFile = serde_json::from_str(&json).map_err(|e| format!("can't parse {}: {}", path.display(), e))?;
+        if map.version != MAP_VERSION {
+            return Err(format!("unsupported map version {} (expected {})", map.version, MAP_VERSION));
+        }
+        map.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        return Ok(map);
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.world_width > 0.0 && self.world_height > 0.0) {
+            return Err(String::from("world size must be positive"));
+        }
+        for data in self.obstacles.iter() {
+            data.to_obstacle()?;
+        }
+        let size = Vec2::new(self.world_width, self.world_height);
+        for placement in self.sources.iter().chain(self.asteroids.iter()) {
+            let pos = Vec2::from_array(placement.pos);
+            if !(pos.cmpge(Vec2::ZERO).all() && pos.cmple(size).all()) {
+                return Err(format!("placement {} at {:?} is outside the world", placement.key, placement.pos));
+            }
+        }
+        let mut keys: Vec<u64> = self.sources.iter().chain(self.asteroids.iter()).map(|placement| placement.key).collect();
+        keys.sort();
+        if let Some(pair) = keys.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(format!("placement key {} is used more than once", pair[0]));
+        }
+        return Ok(());
+    }
+}
+
+pub fn map_path(map_name: &str) -> PathBuf {
+    return Path::new(MAPS_DIR).join(format!("{}.json", file_stem(map_name, "map")));
+}
+
+pub fn list_maps() -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    if let Ok(entries) = fs::read_dir(MAPS_DIR) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    return names;
+}
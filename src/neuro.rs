@@ -1,24 +1,172 @@
-use rand::{Rng, thread_rng};
+use rand::{Rng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 
 
-pub struct DummyNetwork {
-    outputs: usize
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    #[serde(alias = "Linear")]
+    Linear,
+    #[serde(alias = "Sigmoid")]
+    Sigmoid,
+    #[serde(alias = "Tanh")]
+    Tanh,
+    #[serde(alias = "Relu")]
+    Relu,
 }
 
-impl DummyNetwork {
-    pub fn new(outputs_num: usize) -> Self {
+impl Activation {
+    pub const ALL: [Activation; 4] = [Activation::Linear, Activation::Sigmoid, Activation::Tanh, Activation::Relu];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Activation::Linear => "Linear",
+            Activation::Sigmoid => "Sigmoid",
+            Activation::Tanh => "Tanh",
+            Activation::Relu => "ReLU",
+        }
+    }
+
+    pub fn apply(&self, x: f32) -> f32 {
+        match self {
+            Activation::Linear => x,
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            Activation::Tanh => x.tanh(),
+            Activation::Relu => x.max(0.0),
+        }
+    }
+}
+
+//?         [[[LAYER]]]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Layer {
+    pub inputs: usize,
+    pub outputs: usize,
+    //  row-major: one row of input weights for every output neuron
+    pub weights: Vec<f32>,
+    pub biases: Vec<f32>,
+    pub activation: Activation,
+}
+
+impl Layer {
+    pub fn new(inputs: usize, outputs: usize, activation: Activation, rng: &mut StdRng) -> Self {
+        let mut weights: Vec<f32> = vec![];
+        let mut biases: Vec<f32> = vec![];
+        for _ in 0..(inputs * outputs) {
+            weights.push(rng.gen_range(-1.0..1.0));
+        }
+        for _ in 0..outputs {
+            biases.push(rng.gen_range(-1.0..1.0));
+        }
         Self {
-            outputs: outputs_num,
+            inputs,
+            outputs,
+            weights,
+            biases,
+            activation,
         }
     }
 
-    pub fn analize(&self) -> Vec<f32> {
+    pub fn mutate(&mut self, rate: f32, strength: f32, rng: &mut StdRng) {
+        for w in self.weights.iter_mut().chain(self.biases.iter_mut()) {
+            if rng.gen_bool(rate as f64) {
+                *w = (*w + rng.gen_range(-strength..strength)).clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    pub fn crossover(&self, other: &Layer, rng: &mut StdRng) -> Layer {
+        let mut child = self.clone();
+        for (w, w2) in child.weights.iter_mut().zip(other.weights.iter()) {
+            if rng.gen_bool(0.5) {
+                *w = *w2;
+            }
+        }
+        for (b, b2) in child.biases.iter_mut().zip(other.biases.iter()) {
+            if rng.gen_bool(0.5) {
+                *b = *b2;
+            }
+        }
+        return child;
+    }
+
+    pub fn feed(&self, inputs: &[f32]) -> Vec<f32> {
         let mut outputs: Vec<f32> = vec![];
-        let mut rng = thread_rng();
-        for _ in 0..self.outputs {
-           let out = rng.gen_range(-1.0..1.0); 
-           outputs.push(out);
+        for o in 0..self.outputs {
+            let row = &self.weights[o * self.inputs..(o + 1) * self.inputs];
+            let mut sum = self.biases[o];
+            for (w, x) in row.iter().zip(inputs.iter()) {
+                sum += w * x;
+            }
+            outputs.push(self.activation.apply(sum));
         }
         return outputs;
     }
-}
\ No newline at end of file
+}
+
+//?         [[[NETWORK]]]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Network {
+    pub layers: Vec<Layer>,
+}
+
+impl Network {
+    pub fn new(layers_sizes: &[usize], hidden_activation: Activation, output_activation: Activation, rng: &mut StdRng) -> Self {
+        assert!(layers_sizes.len() >= 2, "network needs at least input and output layer");
+        let mut layers: Vec<Layer> = vec![];
+        let last = layers_sizes.len() - 2;
+        for i in 0..=last {
+            let activation = if i == last { output_activation } else { hidden_activation };
+            layers.push(Layer::new(layers_sizes[i], layers_sizes[i + 1], activation, rng));
+        }
+        Self {
+            layers,
+        }
+    }
+
+    pub fn analize(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut signals: Vec<f32> = inputs.to_vec();
+        for layer in self.layers.iter() {
+            signals = layer.feed(&signals);
+        }
+        return signals;
+    }
+
+    pub fn mutate(&mut self, rate: f32, strength: f32, rng: &mut StdRng) {
+        for layer in self.layers.iter_mut() {
+            layer.mutate(rate, strength, rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Network, rng: &mut StdRng) -> Network {
+        let mut layers: Vec<Layer> = vec![];
+        for (layer, layer2) in self.layers.iter().zip(other.layers.iter()) {
+            layers.push(layer.crossover(layer2, rng));
+        }
+        return Network { layers };
+    }
+
+    pub fn inputs_num(&self) -> usize {
+        return self.layers.first().map_or(0, |layer| layer.inputs);
+    }
+
+    pub fn outputs_num(&self) -> usize {
+        return self.layers.last().map_or(0, |layer| layer.outputs);
+    }
+
+    //  shape checks for networks that come from files, `feed` indexes by the declared sizes
+    pub fn validate(&self) -> Result<(), String> {
+        if self.layers.is_empty() {
+            return Err(String::from("network has no layers"));
+        }
+        for (i, layer) in self.layers.iter().enumerate() {
+            if layer.weights.len() != layer.inputs * layer.outputs || layer.biases.len() != layer.outputs {
+                return Err(format!("layer {} doesn't match its {}x{} shape", i, layer.inputs, layer.outputs));
+            }
+            if i > 0 && self.layers[i - 1].outputs != layer.inputs {
+                return Err(format!("layer {} takes {} inputs but gets {}", i, layer.inputs, self.layers[i - 1].outputs));
+            }
+        }
+        return Ok(());
+    }
+}
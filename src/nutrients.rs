@@ -0,0 +1,78 @@
+use macroquad::prelude::*;
+
+
+pub struct NutrientField {
+    pub cell_size: f32,
+    pub cols: usize,
+    pub rows: usize,
+    pub cells: Vec<f32>,
+    pub max_nutrient: f32,
+    pub regen_rate: f32,
+}
+
+impl NutrientField {
+    pub fn new(width: f32, height: f32, cell_size: f32, max_nutrient: f32, regen_rate: f32) -> Self {
+        let cols = (width / cell_size).ceil().max(1.0) as usize;
+        let rows = (height / cell_size).ceil().max(1.0) as usize;
+        Self {
+            cell_size,
+            cols,
+            rows,
+            cells: vec![max_nutrient; cols * rows],
+            max_nutrient,
+            regen_rate,
+        }
+    }
+
+    fn cell_index(&self, pos: Vec2) -> Option<usize> {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return None;
+        }
+        let col = (pos.x / self.cell_size) as usize;
+        let row = (pos.y / self.cell_size) as usize;
+        if col >= self.cols || row >= self.rows {
+            return None;
+        }
+        return Some(row * self.cols + col);
+    }
+
+    pub fn get(&self, pos: Vec2) -> f32 {
+        match self.cell_index(pos) {
+            Some(idx) => {
+                return self.cells[idx];
+            },
+            None => {
+                return 0.0;
+            }
+        }
+    }
+
+    pub fn take(&mut self, pos: Vec2, amount: f32) -> f32 {
+        match self.cell_index(pos) {
+            Some(idx) => {
+                let taken = amount.min(self.cells[idx]).max(0.0);
+                self.cells[idx] -= taken;
+                return taken;
+            },
+            None => {
+                return 0.0;
+            }
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for cell in self.cells.iter_mut() {
+            *cell = (*cell + self.regen_rate * dt).min(self.max_nutrient);
+        }
+    }
+
+    pub fn draw(&self) {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let fertility = self.cells[row * self.cols + col] / self.max_nutrient;
+                let color = Color::new(0.1, 0.4, 0.1, 0.25 * fertility);
+                draw_rectangle(col as f32 * self.cell_size, row as f32 * self.cell_size, self.cell_size, self.cell_size, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Iter;
+
+use macroquad::prelude::*;
+use parry2d::transformation::convex_hull;
+use ::rand::{Rng, rngs::StdRng};
+use crate::util::*;
+use crate::world::*;
+use rapier2d::prelude::RigidBodyHandle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObstacleKind {
+    Polyline,
+    Convex,
+}
+
+//  fixed terrain: blocks movement through its collider and vision through World::line_of_sight
+pub struct Obstacle {
+    pub key: u64,
+    pub kind: ObstacleKind,
+    pub points: Vec<Vec2>,
+    pub physics_handle: Option<RigidBodyHandle>,
+}
+
+impl Obstacle {
+    //  points are in world coordinates, convex shapes keep only their hull
+    pub fn new(kind: ObstacleKind, points: Vec<Vec2>, rng: &mut StdRng) -> Option<Self> {
+        let mut obstacle = Self::with_key(0, kind, points).ok()?;
+        obstacle.key = rng.gen::<u64>();
+        return Some(obstacle);
+    }
+
+    //  same checks as `new` for obstacles restored from snapshots and map files
+    pub fn with_key(key: u64, kind: ObstacleKind, points: Vec<Vec2>) -> Result<Self, String> {
+        if points.iter().any(|p| !p.is_finite()) {
+            return Err(format!("obstacle {}: points must be finite", key));
+        }
+        let points = match kind {
+            ObstacleKind::Polyline => {
+                if points.len() < 2 {
+                    return Err(format!("obstacle {}: a polyline needs at least 2 points", key));
+                }
+                points
+            },
+            ObstacleKind::Convex => {
+                if points.len() < 3 {
+                    return Err(format!("obstacle {}: a convex shape needs at least 3 points", key));
+                }
+                let hull = convex_hull(&vec2_to_point2_collection(&points));
+                if hull.len() < 3 {
+                    return Err(format!("obstacle {}: convex shape is degenerate", key));
+                }
+                hull.iter().map(|p| Vec2::new(p.x, p.y)).collect()
+            },
+        };
+        return Ok(Self {
+            key,
+            kind,
+            points,
+            physics_handle: None,
+        });
+    }
+
+    pub fn contains(&self, pos: Vec2) -> bool {
+        if self.kind != ObstacleKind::Convex {
+            return false;
+        }
+        let n = self.points.len();
+        let mut sign = 0.0;
+        for i in 0..n {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % n];
+            let cross = (b - a).perp_dot(pos - a);
+            if cross.abs() <= f32::EPSILON {
+                continue;
+            }
+            if sign == 0.0 {
+                sign = cross.signum();
+            } else if cross.signum() != sign {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    //  distance from `pos` to the outline, zero inside a convex shape
+    pub fn distance(&self, pos: Vec2) -> f32 {
+        if self.contains(pos) {
+            return 0.0;
+        }
+        let n = self.points.len();
+        if n == 0 {
+            return f32::INFINITY;
+        }
+        let segments = if self.kind == ObstacleKind::Convex { n } else { n - 1 };
+        let mut dist = f32::INFINITY;
+        for i in 0..segments {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % n];
+            let ab = b - a;
+            let t = if ab.length_squared() > 0.0 { ((pos - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0) } else { 0.0 };
+            dist = dist.min(pos.distance(a + ab * t));
+        }
+        return dist;
+    }
+
+    pub fn draw(&self) {
+        let n = self.points.len();
+        match self.kind {
+            ObstacleKind::Polyline => {
+                for i in 1..n {
+                    let (a, b) = (self.points[i - 1], self.points[i]);
+                    draw_line(a.x, a.y, b.x, b.y, 4.0, LIGHTGRAY);
+                }
+            },
+            ObstacleKind::Convex => {
+                let first = self.points[0];
+                for i in 1..n - 1 {
+                    draw_triangle(first, self.points[i], self.points[i + 1], DARKGRAY);
+                }
+                for i in 0..n {
+                    let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+                    draw_line(a.x, a.y, b.x, b.y, 2.0, LIGHTGRAY);
+                }
+            },
+        }
+    }
+}
+
+
+pub struct ObstaclesBox {
+    pub obstacles: BTreeMap<u64, Obstacle>,
+}
+
+impl ObstaclesBox {
+    pub fn new() -> Self {
+        Self {
+            obstacles: BTreeMap::new(),
+        }
+    }
+
+    pub fn add_obstacle(&mut self, mut obstacle: Obstacle, physics_world: &mut World) -> Option<u64> {
+        let key = obstacle.key;
+        let handle = match obstacle.kind {
+            ObstacleKind::Polyline => physics_world.add_fixed_polyline(key, &obstacle.points),
+            ObstacleKind::Convex => physics_world.add_fixed_convex(key, &obstacle.points)?,
+        };
+        obstacle.physics_handle = Some(handle);
+        self.obstacles.insert(key, obstacle);
+        return Some(key);
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Obstacle> {
+        return self.obstacles.get(&id);
+    }
+
+    pub fn remove(&mut self, id: u64, physics_world: &mut World) {
+        if let Some(obstacle) = self.obstacles.remove(&id) {
+            if let Some(handle) = obstacle.physics_handle {
+                physics_world.remove_physics_object(handle);
+            }
+        }
+    }
+
+    pub fn contains(&self, pos: Vec2) -> bool {
+        return self.obstacles.values().any(|obstacle| obstacle.contains(pos));
+    }
+
+    pub fn get_iter(&self) -> Iter<u64, Obstacle> {
+        return self.obstacles.iter();
+    }
+
+    pub fn count(&self) -> usize {
+        return self.obstacles.len();
+    }
+}
@@ -6,87 +6,450 @@ use crate::consts::*;
 use crate::element;
 use crate::kinetic::*;
 use crate::ui::*;
-use crate::util::Signals;
+use crate::util::{random_position, Signals, WorldBounds};
 use crate::world::*;
 use crate::element::*;
+use crate::genome::*;
+use crate::source::*;
+use crate::obstacle::*;
+use crate::map::*;
+use crate::nutrients::*;
+use crate::config::*;
+use crate::snapshot::*;
+use crate::stats::*;
+use crate::timer::*;
 use egui_macroquad;
 use macroquad::camera::Camera2D;
 use macroquad::prelude::*;
+use ::rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 pub struct Simulation {
     pub simulation_name: String,
     pub world_size: Vec2,
-    pub font: Font,
     pub world: World,
-    //zoom_rate: f32,
-    //screen_ratio: f32,
-    pub camera: Camera2D,
     pub running: bool,
     pub sim_time: f64,
     config: SimConfig,
-    pub ui: UISystem,
     pub sim_state: SimState,
     pub signals: Signals,
-    select_phase: f32,
-    pub selected: u64,
-    pub mouse_state: MouseState,
     pub agents: AgentsBox,
     pub elements: DynamicCollector,
+    pub sources: SourcesBox,
+    pub obstacles: ObstaclesBox,
+    pub layout: MapFile,
+    pub nutrients: Option<NutrientField>,
+    autosave_timer: Timer,
+    rng: StdRng,
+    pub stats_dir: Option<PathBuf>,
+    stats: Option<StatsWriter>,
+    stats_timer: Timer,
+    next_config: Option<SimConfig>,
 }
 
 impl Simulation {
-    pub fn new(configuration: SimConfig, font: Font) -> Self {
+    pub fn new(configuration: SimConfig) -> Self {
         Self {
             simulation_name: String::new(),
             world_size: Vec2 {
-                x: WORLD_W,
-                y: WORLD_H,
+                x: configuration.world_width,
+                y: configuration.world_height,
             },
-            font: font,
             world: World::new(),
-            //zoom_rate: 1.0 / 600.0,
-            //screen_ratio: SCREEN_WIDTH / SCREEN_HEIGHT,
-            camera: create_camera(),
-            //camera: Camera2D::default(),
             running: false,
             sim_time: 0.0,
-            config: configuration,
-            ui: UISystem::new(),
+            config: configuration.clone(),
             sim_state: SimState::new(),
             signals: Signals::new(),
-            selected: 0,
-            select_phase: 0.0,
-            mouse_state: MouseState { pos: Vec2::NAN },
             agents: AgentsBox::new(),
             elements: DynamicCollector::new(),
-            //sources: SourcesBox::new(),
+            sources: SourcesBox::new(),
+            obstacles: ObstaclesBox::new(),
+            layout: MapFile::new(),
+            nutrients: None,
+            autosave_timer: Timer::new(configuration.autosave_interval.max(1.0), true, true, 0.0),
+            rng: StdRng::seed_from_u64(configuration.seed),
+            stats_dir: None,
+            stats: None,
+            stats_timer: Timer::new(configuration.stats_interval, true, true, 0.0),
+            next_config: None,
         }
     }
 
-    fn reset_sim(&mut self, sim_name: Option<&str>) {
+    pub fn reload_config(&mut self, watcher: &mut ConfigWatcher) {
+        match watcher.poll() {
+            Some(Ok(config)) => {
+                let notice = self.apply_config(config);
+                println!("{}", notice);
+                self.sim_state.config_notice = notice;
+            },
+            Some(Err(e)) => {
+                let notice = format!("config not reloaded: {}", e);
+                eprintln!("{}", notice);
+                self.sim_state.config_notice = notice;
+            },
+            None => {},
+        }
+    }
+
+    //  applies what's safe to change on a live simulation, the rest waits for the next new simulation
+    pub fn apply_config(&mut self, config: SimConfig) -> String {
+        let changed = self.config.changed_keys(&config);
+        let (restart, applied): (Vec<String>, Vec<String>) = changed.into_iter()
+            .partition(|key| RESTART_KEYS.contains(&key.as_str()));
+        let live = self.config.with_live_keys(&config);
+        if let Some(field) = self.nutrients.as_mut() {
+            field.max_nutrient = live.nutrient_max;
+            field.regen_rate = live.nutrient_regen;
+        }
+        self.autosave_timer.set_duration(live.autosave_interval.max(1.0));
+        self.config = live;
+        self.next_config = if restart.is_empty() { None } else { Some(config) };
+        let mut notice = String::from("config reloaded");
+        if applied.is_empty() && restart.is_empty() {
+            notice.push_str(": no changes");
+        }
+        if !applied.is_empty() {
+            notice.push_str(&format!(", applied: {}", applied.join(", ")));
+        }
+        if !restart.is_empty() {
+            notice.push_str(&format!(", needs restart: {}", restart.join(", ")));
+        }
+        return notice;
+    }
+
+    fn pick_seed(seed: u64) -> u64 {
+        if seed != 0 {
+            return seed;
+        }
+        return StdRng::from_entropy().gen_range(1..u64::MAX);
+    }
+
+    pub fn bounds(&self) -> WorldBounds {
+        return WorldBounds::new(self.world_size, self.config.boundary);
+    }
+
+    //  config the next new simulation will start with
+    pub fn next_config(&self) -> &SimConfig {
+        return self.next_config.as_ref().unwrap_or(&self.config);
+    }
+
+    pub fn reset_sim(&mut self, sim_name: Option<&str>) {
+        if let Some(config) = self.next_config.take() {
+            self.config = config;
+        }
+        self.clear_sim(sim_name);
+        self.apply_layout();
+        self.init();
+        self.open_stats(false);
+        self.running = true;
+    }
+
+    fn clear_sim(&mut self, sim_name: Option<&str>) {
         self.simulation_name = match sim_name {
             Some(name) => name.to_string(),
             None => String::new(),
         };
         self.world = World::new();
+        self.world_size = Vec2::new(self.config.world_width, self.config.world_height);
+        if self.config.boundary == BoundaryMode::Walls {
+            self.world.add_walls(self.world_size);
+        }
         self.agents.agents.clear();
         self.elements.elements.clear();
+        self.sources.sources.clear();
+        self.obstacles.obstacles.clear();
         self.sim_time = 0.0;
         self.sim_state = SimState::new();
         self.sim_state.sim_name = String::from(&self.simulation_name);
         self.signals = Signals::new();
-        self.selected = 0;
-        self.select_phase = 0.0;
-        self.mouse_state = MouseState { pos: Vec2::NAN };
+        self.nutrients = None;
+        self.autosave_timer = Timer::new(self.config.autosave_interval.max(1.0), true, true, 0.0);
+        self.sim_state.seed = Simulation::pick_seed(self.config.seed);
+        self.rng = StdRng::seed_from_u64(self.sim_state.seed);
+        self.stats = None;
+        self.stats_timer = Timer::new(self.config.stats_interval, true, true, 0.0);
+    }
+
+    fn open_stats(&mut self, resume: bool) {
+        let dir = match self.stats_dir.as_ref() {
+            Some(dir) => dir,
+            None => return,
+        };
+        match StatsWriter::open(dir, &self.simulation_name, self.sim_state.seed, resume) {
+            Ok(stats) => self.stats = Some(stats),
+            Err(e) => eprintln!("stats disabled: {}", e),
+        }
+    }
+
+    pub fn save_sim(&self, path: &Path) -> Result<(), String> {
+        let snapshot = SimSnapshot {
+            version: SNAPSHOT_VERSION,
+            sim_state: self.sim_state.clone(),
+            config: self.config.clone(),
+            agents: self.agents.get_iter().map(|(_, agent)| AgentData::from_agent(agent)).collect(),
+            asteroids: self.elements.get_iter().map(|(_, asteroid)| AsteroidData::from_asteroid(asteroid)).collect(),
+            sources: self.sources.get_iter().map(|(_, source)| SourceData::from_source(source)).collect(),
+            obstacles: self.obstacles.get_iter().map(|(_, obstacle)| ObstacleData::from_obstacle(obstacle)).collect(),
+            layout: Some(self.layout.clone()),
+            nutrients: self.nutrients.as_ref().map(|field| field.cells.clone()),
+        };
+        return snapshot.save(path);
+    }
+
+//?         [[[MAP]]]
+    //  editor placements go into the live world and into the layout every new simulation starts from
+    pub fn add_obstacle(&mut self, kind: ObstacleKind, points: Vec<Vec2>) -> Option<u64> {
+        let obstacle = Obstacle::new(kind, points, &mut self.rng)?;
+        let data = ObstacleData::from_obstacle(&obstacle);
+        let key = self.obstacles.add_obstacle(obstacle, &mut self.world)?;
+        self.layout.obstacles.push(data);
+        return Some(key);
+    }
+
+    //  placements outside the world would snap or wrap somewhere else, so they're refused
+    pub fn add_source_at(&mut self, pos: Vec2) -> Option<u64> {
+        if !self.bounds().contains(pos) {
+            return None;
+        }
+        let placement = Placement { key: self.rng.gen::<u64>(), pos: pos.to_array() };
+        self.layout.sources.push(placement);
+        return Some(self.spawn_source_at(&placement));
+    }
+
+    pub fn add_asteroid_at(&mut self, pos: Vec2) -> Option<u64> {
+        if !self.bounds().contains(pos) {
+            return None;
+        }
+        let placement = Placement { key: self.rng.gen::<u64>(), pos: pos.to_array() };
+        self.layout.asteroids.push(placement);
+        return Some(self.spawn_asteroid_at(&placement));
+    }
+
+    //  zones are cut to the world rectangle, nothing is added if too little is left
+    pub fn add_spawn_zone(&mut self, zone: SpawnZone) -> bool {
+        let min = Vec2::from_array(zone.min).max(Vec2::ZERO);
+        let max = Vec2::from_array(zone.max).min(self.world_size);
+        let zone = SpawnZone::from_corners(min, max.max(min));
+        let size = zone.size();
+        if size.x < SPAWN_ZONE_MIN || size.y < SPAWN_ZONE_MIN {
+            return false;
+        }
+        self.layout.spawn_zones.push(zone);
+        return true;
+    }
+
+    fn spawn_source_at(&mut self, placement: &Placement) -> u64 {
+        let mut source = Source::new(&self.config, &mut self.rng);
+        source.key = placement.key;
+        source.pos = Vec2::from_array(placement.pos);
+        return self.sources.add_source(source, &mut self.world);
+    }
+
+    fn spawn_asteroid_at(&mut self, placement: &Placement) -> u64 {
+        let mut asteroid = Asteroid::new(&self.config, &mut self.rng);
+        asteroid.key = placement.key;
+        asteroid.pos = Vec2::from_array(placement.pos);
+        return self.elements.add_element(asteroid, &mut self.world);
+    }
+
+    //  removes what's under `pos`: asteroids and sources first, then placement markers, spawn zones and obstacles
+    pub fn erase_at(&mut self, pos: Vec2) -> bool {
+        let hit = self.elements.get_iter()
+            .find(|(_, asteroid)| asteroid.pos.distance(pos) <= asteroid.size + ERASE_RANGE)
+            .map(|(key, _)| *key)
+            .or_else(|| self.sources.get_iter()
+                .find(|(_, source)| source.pos.distance(pos) <= source.size + ERASE_RANGE)
+                .map(|(key, _)| *key))
+            .or_else(|| self.layout.sources.iter().chain(self.layout.asteroids.iter())
+                .find(|placement| Vec2::from_array(placement.pos).distance(pos) <= ERASE_RANGE)
+                .map(|placement| placement.key));
+        if let Some(key) = hit {
+            self.remove_placed(key);
+            return true;
+        }
+        if let Some(i) = self.layout.spawn_zones.iter().rposition(|zone| zone.contains(pos)) {
+            self.layout.spawn_zones.remove(i);
+            return true;
+        }
+        let obstacle = self.obstacles.get_iter()
+            .find(|(_, obstacle)| obstacle.distance(pos) <= ERASE_RANGE)
+            .map(|(key, _)| *key);
+        if let Some(key) = obstacle {
+            self.obstacles.remove(key, &mut self.world);
+            self.layout.obstacles.retain(|data| data.key != key);
+            return true;
+        }
+        return false;
+    }
+
+    //  drops the live asteroid or source with `key` together with the layout entry that spawned it
+    fn remove_placed(&mut self, key: u64) {
+        if let Some(handle) = self.elements.get(key).and_then(|asteroid| asteroid.physics_handle) {
+            self.world.remove_physics_object(handle);
+        }
+        self.elements.remove(key);
+        if let Some(handle) = self.sources.get(key).and_then(|source| source.physics_handle) {
+            self.world.remove_physics_object(handle);
+        }
+        self.sources.remove(key);
+        self.layout.sources.retain(|placement| placement.key != key);
+        self.layout.asteroids.retain(|placement| placement.key != key);
+    }
+
+    fn apply_layout(&mut self) {
+        let obstacles = self.layout.obstacles.clone();
+        self.restore_obstacles(&obstacles);
+        for placement in self.layout.sources.clone().iter() {
+            self.spawn_source_at(placement);
+        }
+        for placement in self.layout.asteroids.clone().iter() {
+            self.spawn_asteroid_at(placement);
+        }
+    }
+
+    //  loaders validate up front, anything that still fails here is reported and left out
+    fn restore_obstacles(&mut self, obstacles: &[ObstacleData]) {
+        for data in obstacles.iter() {
+            let added = match data.to_obstacle() {
+                Ok(obstacle) => self.obstacles.add_obstacle(obstacle, &mut self.world)
+                    .ok_or(format!("obstacle {}: can't build its collider", data.key)),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = added {
+                eprintln!("skipped {}", e);
+            }
+        }
+    }
+
+    //  drops the layout and the terrain it put in the live world, placed sources and asteroids stay
+    pub fn clear_layout(&mut self) {
+        let keys: Vec<u64> = self.obstacles.obstacles.keys().copied().collect();
+        for key in keys {
+            self.obstacles.remove(key, &mut self.world);
+        }
+        self.layout = MapFile::new();
+    }
+
+    pub fn save_map(&mut self, path: &Path) -> Result<(), String> {
+        self.layout.world_width = self.world_size.x;
+        self.layout.world_height = self.world_size.y;
+        self.layout.boundary = self.config.boundary;
+        return self.layout.save(path);
+    }
+
+    //  starts a new simulation on the map, in the map's world size and boundary mode
+    pub fn load_map(&mut self, path: &Path) -> Result<(), String> {
+        let map = MapFile::load(path)?;
+        let mut config = self.next_config().clone();
+        config.world_width = map.world_width;
+        config.world_height = map.world_height;
+        config.boundary = map.boundary;
+        self.next_config = Some(config);
+        self.layout = map;
+        let sim_name = self.simulation_name.to_owned();
+        self.reset_sim(Some(&sim_name));
+        return Ok(());
+    }
+
+    pub fn load_sim(&mut self, path: &Path) -> Result<(), String> {
+        let snapshot = SimSnapshot::load(path)?;
+        self.config = snapshot.config;
+        self.clear_sim(Some(&snapshot.sim_state.sim_name));
+        self.sim_state = snapshot.sim_state;
+        self.sim_time = self.sim_state.sim_time;
+        //  resumed runs continue on a stream derived from the run seed and the save time
+        self.rng = StdRng::seed_from_u64(self.sim_state.seed ^ self.sim_state.sim_time.to_bits());
+        self.restore_obstacles(&snapshot.obstacles);
+        self.layout = match snapshot.layout {
+            Some(layout) => layout,
+            None => MapFile::from_obstacles(snapshot.obstacles.clone()),
+        };
+        for data in snapshot.agents.iter() {
+            let agent = data.to_agent(&self.config, &mut self.rng);
+            let key = self.agents.add_agent(agent, &mut self.world);
+            if let Some(agent) = self.agents.get(key) {
+                if let Some(handle) = agent.physics_handle {
+                    self.world.set_body_state(handle, agent.pos, agent.rot, agent.velocity(), agent.ang_vel);
+                }
+            }
+        }
+        for data in snapshot.asteroids.iter() {
+            if let Some(asteroid) = data.to_asteroid(&self.config, &mut self.rng) {
+                let key = self.elements.add_element(asteroid, &mut self.world);
+                if let Some(asteroid) = self.elements.get(key) {
+                    if let Some(handle) = asteroid.physics_handle {
+                        self.world.set_body_state(handle, asteroid.pos, asteroid.rot, asteroid.lin_vel, asteroid.ang_vel);
+                    }
+                }
+            }
+        }
+        for data in snapshot.sources.iter() {
+            self.sources.add_source(data.to_source(&self.config, &mut self.rng), &mut self.world);
+        }
+        if self.config.nutrients {
+            let mut field = NutrientField::new(
+                self.world_size.x,
+                self.world_size.y,
+                self.config.nutrient_cell_size,
+                self.config.nutrient_max,
+                self.config.nutrient_regen,
+            );
+            if let Some(cells) = snapshot.nutrients {
+                if cells.len() == field.cells.len() {
+                    field.cells = cells;
+                }
+            }
+            self.nutrients = Some(field);
+        }
+        self.open_stats(true);
         self.running = true;
+        return Ok(());
     }
 
     pub fn init(&mut self) {
         let agents_num = self.config.agents_init_num;
-        self.agents.add_many_agents(agents_num as usize, &mut self.world);
-        self.elements.add_many_elements(ASTER_NUM, &mut self.world);
-        //self.sources.add_many(48);
+        for _ in 0..agents_num {
+            self.spawn_agent();
+        }
+        self.elements.add_many_elements(self.config.asteroids_num, &mut self.world, &self.config, &mut self.rng);
+        self.sources.add_many(self.config.sources_init_num, &mut self.world, &self.config, &mut self.rng);
+        self.nutrients = None;
+        if self.config.nutrients {
+            self.nutrients = Some(NutrientField::new(
+                self.world_size.x,
+                self.world_size.y,
+                self.config.nutrient_cell_size,
+                self.config.nutrient_max,
+                self.config.nutrient_regen,
+            ));
+        }
+    }
+
+    //  inside a spawn zone when there are any, never inside an obstacle if a free spot turns up
+    fn spawn_position(&mut self) -> Vec2 {
+        let mut pos = Vec2::ZERO;
+        for _ in 0..SPAWN_TRIES {
+            pos = if self.layout.spawn_zones.is_empty() {
+                random_position(self.world_size.x, self.world_size.y, &mut self.rng)
+            } else {
+                let i = self.rng.gen_range(0..self.layout.spawn_zones.len());
+                self.layout.spawn_zones[i].random_position(&mut self.rng)
+            };
+            if !self.obstacles.contains(pos) {
+                break;
+            }
+        }
+        return pos;
+    }
+
+    pub fn spawn_agent(&mut self) -> u64 {
+        let mut agent = Agent::new(&self.config, &mut self.rng);
+        agent.pos = self.spawn_position();
+        return self.agents.add_agent(agent, &mut self.world);
     }
 
     pub fn autorun_new_sim(&mut self) {
@@ -95,106 +458,346 @@ impl Simulation {
     }
 
     fn update_agents(&mut self) {
+        let bounds = self.bounds();
         for (id, agent) in self.agents.get_iter_mut() {
-            agent.update2(&mut self.world);
+            agent.update2(&mut self.world, &bounds);
         }
         let dt = self.sim_state.dt;
+        let mut offspring: Vec<Agent> = vec![];
+        let mut corpses: Vec<Source> = vec![];
         for (id, agent) in self.agents.get_iter_mut() {
             let uid = *id;
-            if !agent.update(dt, &self.world) {
+            if !agent.update(dt, self.config.agent_metabolism, &self.world, &bounds) {
                 match agent.physics_handle {
                     Some(handle) => {
                         self.world.remove_physics_object(handle);
                     }
                     None => {}
                 }
-            };
+                let eng = agent.max_eng * self.config.corpse_eng_ratio;
+                if eng > 0.0 {
+                    corpses.push(Source::new_corpse(agent.pos, agent.size, eng, &self.config, &mut self.rng));
+                }
+            } else {
+                Simulation::feed_agent(agent, &mut self.sources, self.config.agent_eat_rate * dt);
+                if agent.can_replicate(self.config.repro_eng_threshold) {
+                    let child = agent.replicate(&self.config, &bounds, &mut self.rng);
+                    if !self.obstacles.contains(child.pos) {
+                        agent.commit_replication(agent.replication_cost(&self.config));
+                        offspring.push(child);
+                    }
+                }
+            }
         }
         self.agents.agents.retain(|_, agent| agent.alife == true);
+        for agent in offspring {
+            self.agents.add_agent(agent, &mut self.world);
+        }
+        for corpse in corpses {
+            self.sources.add_source(corpse, &mut self.world);
+        }
+    }
+
+    fn feed_agent(agent: &mut Agent, sources: &mut SourcesBox, max_eng: f32) {
+        for key in agent.contacts.clone() {
+            match sources.sources.get_mut(&key) {
+                Some(source) => {
+                    let hunger = agent.max_eng - agent.eng;
+                    let eng = source.drain_eng(max_eng.min(hunger));
+                    agent.add_energy(eng);
+                },
+                None => {},
+            }
+        }
+    }
+
+    fn update_sources(&mut self) {
+        let bounds = self.bounds();
+        let dt = self.sim_state.dt;
+        if let Some(field) = self.nutrients.as_mut() {
+            field.update(dt);
+        }
+        let mut seedlings: Vec<Source> = vec![];
+        let mut sources_num = self.sources.count_kind(SourceKind::Plant);
+        for (id, source) in self.sources.get_iter_mut() {
+            let demand = source.growth_demand(self.config.source_growth_rate, dt);
+            let growth = match self.nutrients.as_mut() {
+                Some(field) => field.take(source.pos, demand),
+                None => demand,
+            };
+            source.update(dt, growth, self.config.source_seed_interval, self.config.source_seed_min_eng, &mut self.world, &bounds);
+            if source.alife && source.seed_ready && sources_num < self.config.sources_max_num {
+                let seedling = source.spawn_seedling(&self.config, &mut self.rng);
+                let fertile = match self.nutrients.as_ref() {
+                    Some(field) => field.get(seedling.pos) >= seedling.eng,
+                    None => true,
+                };
+                if fertile {
+                    source.commit_seed(source.seed_cost(&self.config));
+                    seedlings.push(seedling);
+                    sources_num += 1;
+                }
+            }
+            if !source.alife {
+                match source.physics_handle {
+                    Some(handle) => {
+                        self.world.remove_physics_object(handle);
+                    }
+                    None => {}
+                }
+            }
+        }
+        self.sources.sources.retain(|_, source| source.alife);
+        for source in seedlings {
+            self.sources.add_source(source, &mut self.world);
+        }
+    }
+
+    fn update_predation(&mut self) {
+        let dt = self.sim_state.dt;
+        let mut attacks: Vec<(u64, u64)> = vec![];
+        for (id, agent) in self.agents.get_iter_mut() {
+            if !agent.alife || !agent.attacking {
+                continue;
+            }
+            agent.take_damage(self.config.attack_cost * dt);
+            for key in agent.contacts.iter() {
+                attacks.push((*id, *key));
+            }
+        }
+        for (attacker_key, victim_key) in attacks {
+            let eng = match self.agents.agents.get_mut(&victim_key) {
+                Some(victim) if victim.alife => {
+                    let eng = (self.config.attack_damage * dt).min(victim.eng);
+                    victim.take_damage(eng);
+                    eng
+                },
+                _ => continue,
+            };
+            if let Some(attacker) = self.agents.agents.get_mut(&attacker_key) {
+                attacker.add_energy(eng * self.config.attack_efficiency);
+            }
+        }
+    }
+
+    fn update_mating(&mut self) {
+        let bounds = self.bounds();
+        let mut paired: Vec<u64> = vec![];
+        let mut offspring: Vec<Agent> = vec![];
+        let keys: Vec<u64> = self.agents.agents.keys().copied().collect();
+        for key in keys {
+            if paired.contains(&key) {
+                continue;
+            }
+            let mut partner_key: Option<u64> = None;
+            let mut child_genome: Option<Genome> = None;
+            match self.agents.get(key) {
+                Some(agent) if agent.wants_mate(self.config.mate_min_eng) => {
+                    let handle = match agent.physics_handle {
+                        Some(handle) => handle,
+                        None => continue,
+                    };
+                    for other_key in self.world.get_objects_in_range(handle, &bounds) {
+                        if other_key == key || paired.contains(&other_key) {
+                            continue;
+                        }
+                        match self.agents.get(other_key) {
+                            Some(partner) if partner.wants_mate(self.config.mate_min_eng)
+                                && agent.genome.distance(&partner.genome, &self.config) <= self.config.mate_max_distance => {
+                                let mut genome = agent.genome.crossover(&partner.genome, &mut self.rng);
+                                genome.mutate(&self.config, &mut self.rng);
+                                partner_key = Some(other_key);
+                                child_genome = Some(genome);
+                                break;
+                            },
+                            _ => {},
+                        }
+                    }
+                },
+                _ => {},
+            }
+            if let (Some(partner_key), Some(genome)) = (partner_key, child_genome) {
+                let mut child = Agent::from_genome(genome, &self.config, &mut self.rng);
+                let mut eng = 0.0;
+                let mut parents: Vec<Vec2> = vec![];
+                for k in [key, partner_key] {
+                    if let Some(parent) = self.agents.agents.get_mut(&k) {
+                        eng += parent.mated(self.config.mate_eng_cost, self.config.mate_cooldown);
+                        parents.push(parent.pos);
+                    }
+                }
+                //  midpoint of the nearest images, partners can pair across a seam
+                let pos = match parents[..] {
+                    [a, b] => a + bounds.delta(a, b) / 2.0,
+                    [a] => a,
+                    _ => Vec2::ZERO,
+                };
+                child.eng = eng.min(child.max_eng);
+                child.pos = match bounds.confine(pos, Vec2::ZERO) {
+                    Some((pos, _)) => pos,
+                    None => pos,
+                };
+                offspring.push(child);
+                paired.push(key);
+                paired.push(partner_key);
+            }
+        }
+        for agent in offspring {
+            self.agents.add_agent(agent, &mut self.world);
+        }
     }
 
     fn update_elements(&mut self) {
+        let bounds = self.bounds();
         self.sim_state.asteroids_num = self.elements.elements.len();
         for (id, elem) in self.elements.get_iter_mut() {
-            elem.update(self.sim_state.dt, &mut self.world);
+            elem.update(self.sim_state.dt, &mut self.world, &bounds);
         }
     }
 
-    pub fn update(&mut self) {
-        self.signals_check();
+    pub fn step(&mut self) {
         self.update_sim_state();
         self.check_agents_num();
-        self.calc_selection_time();
         self.update_agents();
+        self.update_predation();
+        self.update_mating();
+        self.update_sources();
         self.update_elements();
         self.world.step_physics();
+        self.update_impacts();
+        self.update_autosave();
+        self.update_stats();
     }
 
-    pub fn draw(&self) {
-        //set_default_camera();
-        set_camera(&self.camera);
-        clear_background(BLACK);
-        draw_rectangle_lines(0.0, 0.0, self.world_size.x, self.world_size.y, 3.0, WHITE);
-        self.draw_grid(50);
-        self.draw_agents();
-        self.draw_elements();
+    fn update_stats(&mut self) {
+        self.stats_timer.set_duration(self.config.stats_interval);
+        if !self.stats_timer.update(self.sim_state.dt) {
+            return;
+        }
+        if let Some(stats) = self.stats.as_mut() {
+            if let Err(e) = stats.record(&self.sim_state) {
+                eprintln!("{}", e);
+                self.stats = None;
+            }
+        }
     }
 
-    fn draw_agents(&self) {
-        for (id, agent) in self.agents.get_iter() {
-            let mut draw_field_of_view: bool = false;
-            if *id == self.selected {
-                draw_field_of_view = true;
-            };
-            agent.draw(draw_field_of_view);
+    fn update_autosave(&mut self) {
+        if self.config.autosave_interval <= 0.0 {
+            return;
+        }
+        if self.autosave_timer.update(self.sim_state.dt) {
+            self.autosave();
         }
-        match self.agents.get(self.selected) {
-            Some(selected_agent) => {
-                let pos = Vec2::new(selected_agent.pos.x, selected_agent.pos.y);
-                let s = selected_agent.size;
-                draw_circle_lines(
-                    pos.x,
-                    pos.y,
-                    2.0 * s + (self.select_phase.sin() * s * 0.5),
-                    1.0,
-                    ORANGE,
-                );
-            }
-            None => {}
-        };
     }
 
-    fn draw_elements(&self) {
-        for (id, element) in self.elements.get_iter() {
-            element.draw(self.font);
+    pub fn autosave(&self) {
+        let path = autosave_path(&self.simulation_name, self.sim_state.sim_time);
+        match self.save_sim(&path) {
+            Ok(_) => rotate_autosaves(&self.simulation_name, self.config.autosave_keep.max(1)),
+            Err(e) => eprintln!("autosave failed: {}", e),
         }
     }
 
-    fn draw_grid(&self, cell_size: u32) {
-        let w = self.world_size.x;
-        let h = self.world_size.y;
-        let col_num = (w / cell_size as f32).floor() as u32;
-        let row_num = (h / cell_size as f32).floor() as u32;
-        //draw_grid(100, 20.0, GRAY, DARKGRAY);
-        for x in 0..col_num + 1 {
-            for y in 0..row_num + 1 {
-                draw_circle((x * cell_size) as f32, (y * cell_size) as f32, 1.0, GRAY);
+    fn update_impacts(&mut self) {
+        for (key1, key2) in self.world.new_contacts.iter() {
+            for (agent_key, asteroid_key) in [(*key1, *key2), (*key2, *key1)] {
+                let asteroid = match self.elements.get(asteroid_key) {
+                    Some(asteroid) => asteroid,
+                    None => continue,
+                };
+                match self.agents.agents.get_mut(&agent_key) {
+                    Some(agent) => {
+                        let eng = impact_energy(agent.mass, agent.velocity(), asteroid.mass, asteroid.lin_vel);
+                        agent.take_damage(eng * self.config.impact_damage);
+                    },
+                    None => {},
+                }
             }
         }
     }
 
     pub fn signals_check(&mut self) {
         if self.signals.spawn_agent {
-            let agent = Agent::new();
-            self.agents.add_agent(agent, &mut self.world);
+            self.spawn_agent();
             self.signals.spawn_agent = false;
         }
         if self.signals.new_sim {
             self.signals.new_sim = false;
+            if let Some(config) = self.signals.new_sim_config.take() {
+                self.next_config = Some(config);
+            }
             //if !self.signals.new_sim_name.is_empty() {
             self.reset_sim(Some(&self.signals.new_sim_name.to_owned()));
             //}
         }
+        if self.signals.save_sim {
+            self.signals.save_sim = false;
+            let path = save_path(&self.simulation_name);
+            match self.save_sim(&path) {
+                Ok(_) => println!("simulation saved to {}", path.display()),
+                Err(e) => eprintln!("save failed: {}", e),
+            }
+        }
+        if self.signals.load_autosave {
+            self.signals.load_autosave = false;
+            let sim_name = self.signals.load_sim_name.to_owned();
+            match latest_autosave(&sim_name) {
+                Some(path) => match self.load_sim(&path) {
+                    Ok(_) => println!("simulation resumed from {}", path.display()),
+                    Err(e) => eprintln!("resume failed: {}", e),
+                },
+                None => eprintln!("no autosave found for {}", sim_name),
+            }
+        }
+        if self.signals.pause_sim {
+            self.signals.pause_sim = false;
+            self.running = !self.running;
+        }
+        if self.signals.step_sim {
+            self.signals.step_sim = false;
+            if !self.running {
+                self.step();
+            }
+        }
+        if self.signals.quit {
+            self.signals.quit = false;
+            if self.sim_state.sim_time > 0.0 && self.config.autosave_interval > 0.0 {
+                self.autosave();
+            }
+            if let Some(stats) = self.stats.as_mut() {
+                stats.flush();
+            }
+            std::process::exit(0);
+        }
+        if self.signals.save_map {
+            self.signals.save_map = false;
+            let path = map_path(&self.signals.map_name.to_owned());
+            match self.save_map(&path) {
+                Ok(_) => println!("map saved to {}", path.display()),
+                Err(e) => eprintln!("map save failed: {}", e),
+            }
+        }
+        if self.signals.load_map {
+            self.signals.load_map = false;
+            let path = map_path(&self.signals.map_name.to_owned());
+            match self.load_map(&path) {
+                Ok(_) => println!("map loaded from {}", path.display()),
+                Err(e) => eprintln!("map load failed: {}", e),
+            }
+        }
+        if self.signals.clear_map {
+            self.signals.clear_map = false;
+            self.clear_layout();
+        }
+        if self.signals.load_sim {
+            self.signals.load_sim = false;
+            let path = save_path(&self.signals.load_sim_name.to_owned());
+            match self.load_sim(&path) {
+                Ok(_) => println!("simulation loaded from {}", path.display()),
+                Err(e) => eprintln!("load failed: {}", e),
+            }
+        }
     }
 
     /* fn get_selected(&self) -> Option<&Agent> {
@@ -208,57 +811,29 @@ impl Simulation {
         };
     } */
 
-    pub fn input(&mut self) {
-        self.mouse_input();
-        control_camera(&mut self.camera);
-    }
-
-    fn mouse_input(&mut self) {
-        if is_mouse_button_released(MouseButton::Left) {
-            if !self.ui.pointer_over {
-                self.selected = 0;
-                let (mouse_posx, mouse_posy) = mouse_position();
-                let mouse_pos = Vec2::new(mouse_posx, mouse_posy);
-                let rel_coords = self.camera.screen_to_world(mouse_pos);
-                for (id, agent) in self.agents.get_iter() {
-                    if contact_mouse(rel_coords, agent.pos, agent.size) {
-                        self.selected = *id;
-                        break;
-                    }
-                }
-            }
-        }
-    }
-
     fn update_sim_state(&mut self) {
-        self.sim_state.fps = get_fps();
-        self.sim_state.dt = get_frame_time();
+        self.sim_state.dt = FIX_DT;
         self.sim_state.sim_time += self.sim_state.dt as f64;
-        let (mouse_x, mouse_y) = mouse_position();
-        self.mouse_state.pos = Vec2::new(mouse_x, mouse_y);
         self.sim_state.agents_num = self.agents.count() as i32;
+        self.sim_state.sources_num = self.sources.count_kind(SourceKind::Plant) as i32;
+        self.sim_state.corpses_num = self.sources.count_kind(SourceKind::Corpse) as i32;
         self.sim_state.physics_num = self.world.get_physics_obj_num() as i32;
     }
 
     fn check_agents_num(&mut self) {
         if self.sim_state.agents_num < (self.config.agent_min_num as i32) {
-            let agent = Agent::new();
-            self.agents.add_agent(agent, &mut self.world);
+            self.spawn_agent();
+        }
+        if self.sim_state.sources_num < (self.config.sources_min_num as i32) {
+            let source = Source::new(&self.config, &mut self.rng);
+            self.sources.add_source(source, &mut self.world);
         }
-        /* if self.sim_state.sources_num < (self.config.sources_min_num as i32) {
-            let source = Source::new();
-        } */
-        if self.sim_state.asteroids_num < (ASTER_NUM) {
-            let asteroid = Asteroid::new();
+        if self.sim_state.asteroids_num < self.config.asteroids_num {
+            let asteroid = Asteroid::new(&self.config, &mut self.rng);
             self.elements.add_element(asteroid, &mut self.world);
         }
     }
 
-    fn calc_selection_time(&mut self) {
-        self.select_phase += self.sim_state.dt * 4.0;
-        self.select_phase = self.select_phase % (2.0 * PI as f32);
-    }
-
     /* fn map_detections(&self) -> DetectionsMap {
         let mut detections = DetectionsMap::new();
         for (id1, agent1) in self.agents.get_iter() {
@@ -323,74 +898,22 @@ impl Simulation {
         return hits;
     } */
 
-    pub fn process_ui(&mut self) {
-        let marked_agent = self.agents.get(self.selected);
-        self.ui
-            .ui_process(&self.sim_state, marked_agent, &mut self.signals, &self.camera);
-    }
-
-    pub fn draw_ui(&self) {
-        self.ui.ui_draw();
-    }
-
     pub fn is_running(&self) -> bool {
         return self.running;
     }
 }
 
-//?         [[[SIM_CONFIG]]]
-#[derive(Clone, Copy)]
-pub struct SimConfig {
-    pub agents_init_num: usize,
-    pub agent_min_num: usize,
-    pub agent_speed: f32,
-    pub agent_vision_range: f32,
-    pub agent_rotation: f32,
-    pub sources_init_num: usize,
-    pub sources_min_num: usize,
-}
-
-impl Default for SimConfig {
-    fn default() -> Self {
-        Self {
-            agents_init_num: AGENTS_NUM,
-            agent_min_num: AGENTS_NUM_MIN,
-            agent_speed: AGENT_SPEED,
-            agent_rotation: AGENT_ROTATION,
-            agent_vision_range: AGENT_VISION_RANGE,
-            sources_init_num: 0,
-            sources_min_num: 0,
-        }
-    }
-}
-
-impl SimConfig {
-    pub fn new(
-        agents_num: usize,
-        agents_min_num: usize,
-        agent_speed: f32,
-        agent_turn: f32,
-        vision_range: f32,
-        sources_num: usize,
-        sources_min_num: usize,
-    ) -> Self {
-        Self {
-            agents_init_num: agents_num,
-            agent_min_num: agents_min_num,
-            agent_speed: agent_speed,
-            agent_rotation: agent_turn,
-            agent_vision_range: vision_range,
-            sources_init_num: sources_num,
-            sources_min_num: sources_min_num,
-        }
-    }
-}
-
 //?         [[[SIM_STATE]]]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SimState {
     pub sim_name: String,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(skip)]
+    pub config_notice: String,
     pub agents_num: i32,
     pub sources_num: i32,
+    pub corpses_num: i32,
     pub asteroids_num: usize,
     pub physics_num: i32,
     pub sim_time: f64,
@@ -402,8 +925,11 @@ impl SimState {
     pub fn new() -> Self {
         Self {
             sim_name: String::new(),
+            seed: 0,
+            config_notice: String::new(),
             agents_num: 0,
             sources_num: 0,
+            corpses_num: 0,
             asteroids_num: 0,
             physics_num: 0,
             sim_time: 0.0,
@@ -413,7 +939,37 @@ impl SimState {
     }
 }
 
-//?         [[[MOUSESTATE]]]
-pub struct MouseState {
-    pub pos: Vec2,
+//?         [[[SIM_SPEED]]]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SimSpeed {
+    X1,
+    X2,
+    X5,
+    X20,
+    Max,
+}
+
+impl SimSpeed {
+    pub const ALL: [SimSpeed; 5] = [SimSpeed::X1, SimSpeed::X2, SimSpeed::X5, SimSpeed::X20, SimSpeed::Max];
+
+    //  fixed steps per FIX_DT of real time, None means as fast as possible
+    pub fn multiplier(&self) -> Option<u32> {
+        match self {
+            SimSpeed::X1 => Some(1),
+            SimSpeed::X2 => Some(2),
+            SimSpeed::X5 => Some(5),
+            SimSpeed::X20 => Some(20),
+            SimSpeed::Max => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SimSpeed::X1 => "1x",
+            SimSpeed::X2 => "2x",
+            SimSpeed::X5 => "5x",
+            SimSpeed::X20 => "20x",
+            SimSpeed::Max => "MAX",
+        }
+    }
 }
@@ -0,0 +1,332 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use macroquad::prelude::*;
+use parry2d::shape::*;
+use ::rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use crate::agent::*;
+use crate::config::*;
+use crate::consts::*;
+use crate::element::*;
+use crate::genome::*;
+use crate::map::MapFile;
+use crate::obstacle::*;
+use crate::sim::*;
+use crate::source::*;
+use crate::util::*;
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Color")]
+pub struct ColorDef {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+//?         [[[SIM_SNAPSHOT]]]
+#[derive(Serialize, Deserialize)]
+pub struct SimSnapshot {
+    pub version: u32,
+    pub sim_state: SimState,
+    pub config: SimConfig,
+    pub agents: Vec<AgentData>,
+    pub asteroids: Vec<AsteroidData>,
+    pub sources: Vec<SourceData>,
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleData>,
+    #[serde(default)]
+    pub layout: Option<MapFile>,
+    pub nutrients: Option<Vec<f32>>,
+}
+
+impl SimSnapshot {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {}", dir.display(), e))?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| format!("can't serialize simulation: {}", e))?;
+        fs::write(path, json).map_err(|e| format!("can't write {}: {}", path.display(), e))?;
+        return Ok(());
+    }
+
+    pub fn load(path: &Path) -> Result<SimSnapshot, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let snapshot: SimSnapshot = serde_json::from_str(&json).map_err(|e| format!("can't parse {}: {}", path.display(), e))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(format!("unsupported snapshot version {} (expected {})", snapshot.version, SNAPSHOT_VERSION));
+        }
+        snapshot.config.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        for data in snapshot.agents.iter() {
+            data.genome.validate().map_err(|e| format!("{}: agent {}: {}", path.display(), data.key, e))?;
+        }
+        for data in snapshot.obstacles.iter() {
+            data.to_obstacle().map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        if let Some(layout) = snapshot.layout.as_ref() {
+            layout.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+        return Ok(snapshot);
+    }
+}
+
+pub fn save_path(sim_name: &str) -> PathBuf {
+    return Path::new(SAVES_DIR).join(format!("{}.json", file_stem(sim_name, "simulation")));
+}
+
+pub fn list_saves() -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    if let Ok(entries) = fs::read_dir(SAVES_DIR) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    names.sort();
+    return names;
+}
+
+pub fn autosave_dir(sim_name: &str) -> PathBuf {
+    return Path::new(SAVES_DIR).join(file_stem(sim_name, "simulation"));
+}
+
+pub fn autosave_path(sim_name: &str, sim_time: f64) -> PathBuf {
+    let file_name = format!("autosave_{:010}.json", sim_time.max(0.0) as u64);
+    return autosave_dir(sim_name).join(file_name);
+}
+
+fn autosave_files(dir: &Path) -> Vec<(SystemTime, PathBuf)> {
+    let mut files: Vec<(SystemTime, PathBuf)> = vec![];
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_autosave = path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with("autosave_"));
+            if !is_autosave {
+                continue;
+            }
+            if let Ok(modified) = entry.metadata().and_then(|meta| meta.modified()) {
+                files.push((modified, path));
+            }
+        }
+    }
+    files.sort();
+    return files;
+}
+
+pub fn rotate_autosaves(sim_name: &str, keep: usize) {
+    let files = autosave_files(&autosave_dir(sim_name));
+    if files.len() <= keep {
+        return;
+    }
+    let excess = files.len() - keep;
+    for (_, path) in files.iter().take(excess) {
+        _ = fs::remove_file(path);
+    }
+}
+
+pub fn latest_autosave(sim_name: &str) -> Option<PathBuf> {
+    let files = autosave_files(&autosave_dir(sim_name));
+    return files.last().map(|(_, path)| path.clone());
+}
+
+pub fn list_autosaves() -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    if let Ok(entries) = fs::read_dir(SAVES_DIR) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() || autosave_files(&path).is_empty() {
+                continue;
+            }
+            if let Some(name) = path.file_name() {
+                names.push(name.to_string_lossy().to_string());
+            }
+        }
+    }
+    names.sort();
+    return names;
+}
+
+//?         [[[AGENT_DATA]]]
+#[derive(Serialize, Deserialize)]
+pub struct AgentData {
+    pub key: u64,
+    pub pos: [f32; 2],
+    pub rot: f32,
+    pub vel: f32,
+    pub ang_vel: f32,
+    pub eng: f32,
+    pub mate_ready: bool,
+    pub genome: Genome,
+    //  timer progress, snapshots without it get fresh random phases
+    #[serde(default)]
+    pub analize_time: Option<f32>,
+    #[serde(default)]
+    pub mate_time: Option<f32>,
+}
+
+impl AgentData {
+    pub fn from_agent(agent: &Agent) -> Self {
+        Self {
+            key: agent.key,
+            pos: agent.pos.to_array(),
+            rot: agent.rot,
+            vel: agent.vel,
+            ang_vel: agent.ang_vel,
+            eng: agent.eng,
+            mate_ready: agent.mate_ready,
+            genome: agent.genome.clone(),
+            analize_time: Some(agent.analize_timer.time),
+            mate_time: Some(agent.mate_timer.time),
+        }
+    }
+
+    pub fn to_agent(&self, config: &SimConfig, rng: &mut StdRng) -> Agent {
+        let mut agent = Agent::from_genome(self.genome.clone(), config, rng);
+        agent.key = self.key;
+        agent.pos = Vec2::from_array(self.pos);
+        agent.rot = self.rot;
+        agent.vel = self.vel;
+        agent.ang_vel = self.ang_vel;
+        agent.eng = self.eng;
+        agent.mate_ready = self.mate_ready;
+        if let Some(time) = self.analize_time {
+            agent.analize_timer.time = time;
+        }
+        if let Some(time) = self.mate_time {
+            agent.mate_timer.time = time;
+        }
+        return agent;
+    }
+}
+
+//?         [[[ASTEROID_DATA]]]
+#[derive(Serialize, Deserialize)]
+pub struct AsteroidData {
+    pub key: u64,
+    pub pos: [f32; 2],
+    pub rot: f32,
+    pub lin_vel: [f32; 2],
+    pub ang_vel: f32,
+    pub size: f32,
+    #[serde(with = "ColorDef")]
+    pub color: Color,
+    pub points: Vec<[f32; 2]>,
+}
+
+impl AsteroidData {
+    pub fn from_asteroid(asteroid: &Asteroid) -> Self {
+        Self {
+            key: asteroid.key,
+            pos: asteroid.pos.to_array(),
+            rot: asteroid.rot,
+            lin_vel: asteroid.lin_vel.to_array(),
+            ang_vel: asteroid.ang_vel,
+            size: asteroid.size,
+            color: asteroid.color,
+            points: asteroid.points.iter().map(|p| p.to_array()).collect(),
+        }
+    }
+
+    pub fn to_asteroid(&self, config: &SimConfig, rng: &mut StdRng) -> Option<Asteroid> {
+        let points: Vec<Vec2> = self.points.iter().map(|p| Vec2::from_array(*p)).collect();
+        let points2 = vec2_to_point2_collection(&points);
+        let shape = ConvexPolygon::from_convex_polyline(points2.clone())?;
+        let mut asteroid = Asteroid::new(config, rng);
+        asteroid.key = self.key;
+        asteroid.pos = Vec2::from_array(self.pos);
+        asteroid.rot = self.rot;
+        asteroid.lin_vel = Vec2::from_array(self.lin_vel);
+        asteroid.ang_vel = self.ang_vel;
+        asteroid.size = self.size;
+        asteroid.color = self.color;
+        asteroid.points = points;
+        asteroid.points2 = points2;
+        asteroid.shape = shape;
+        return Some(asteroid);
+    }
+}
+
+//?         [[[OBSTACLE_DATA]]]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObstacleData {
+    pub key: u64,
+    pub kind: ObstacleKind,
+    pub points: Vec<[f32; 2]>,
+}
+
+impl ObstacleData {
+    pub fn from_obstacle(obstacle: &Obstacle) -> Self {
+        Self {
+            key: obstacle.key,
+            kind: obstacle.kind,
+            points: obstacle.points.iter().map(|p| p.to_array()).collect(),
+        }
+    }
+
+    pub fn to_obstacle(&self) -> Result<Obstacle, String> {
+        let points = self.points.iter().map(|p| Vec2::from_array(*p)).collect();
+        return Obstacle::with_key(self.key, self.kind, points);
+    }
+}
+
+//?         [[[SOURCE_DATA]]]
+#[derive(Serialize, Deserialize)]
+pub struct SourceData {
+    pub key: u64,
+    pub kind: SourceKind,
+    pub pos: [f32; 2],
+    pub size: f32,
+    pub max_eng: f32,
+    pub eng: f32,
+    pub decay: f32,
+    #[serde(with = "ColorDef")]
+    pub color: Color,
+    #[serde(default)]
+    pub seed_ready: bool,
+    #[serde(default)]
+    pub seed_time: Option<f32>,
+}
+
+impl SourceData {
+    pub fn from_source(source: &Source) -> Self {
+        Self {
+            key: source.key,
+            kind: source.kind,
+            pos: source.pos.to_array(),
+            size: source.size,
+            max_eng: source.max_eng,
+            eng: source.eng,
+            decay: source.decay,
+            color: source.color,
+            seed_ready: source.seed_ready,
+            seed_time: Some(source.seed_timer.time),
+        }
+    }
+
+    pub fn to_source(&self, config: &SimConfig, rng: &mut StdRng) -> Source {
+        let mut source = Source::new(config, rng);
+        source.key = self.key;
+        source.kind = self.kind;
+        source.pos = Vec2::from_array(self.pos);
+        source.size = self.size;
+        source.shape = Ball { radius: self.size };
+        source.max_eng = self.max_eng;
+        source.eng = self.eng;
+        source.decay = self.decay;
+        source.color = self.color;
+        source.seed_ready = self.seed_ready;
+        if let Some(time) = self.seed_time {
+            source.seed_timer.time = time;
+        }
+        return source;
+    }
+}
@@ -1,19 +1,31 @@
 //#![allow(unused)]
 
-use std::collections::HashMap;
-use std::collections::hash_map::Iter;
-use std::collections::hash_map::IterMut;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Iter;
+use std::collections::btree_map::IterMut;
 use std::f32::consts::PI;
 
 use macroquad::{prelude::*, color}; 
 use parry2d::shape::*;
-use ::rand::{Rng, thread_rng};
+use ::rand::{Rng, rngs::StdRng};
 use crate::kinetic::{Detection, contact_circles};
 use crate::util::*;
+use crate::config::SimConfig;
 use crate::consts::*;
 use crate::timer::*;
+use crate::world::*;
+use rapier2d::prelude::RigidBodyHandle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceKind {
+    Plant,
+    Corpse,
+}
 
 pub struct Source {
+    pub key: u64,
+    pub kind: SourceKind,
     pub pos: Vec2,
     pub rot: f32,
     pub size: f32,
@@ -22,37 +34,107 @@ pub struct Source {
     pub color: color::Color,
     pub shape: Ball,
     pub alife: bool,
+    pub decay: f32,
+    pub seed_ready: bool,
+    pub seed_timer: Timer,
+    pub physics_handle: Option<RigidBodyHandle>,
 }
 
 impl Source {
-    pub fn new() -> Self {
-        let s = rand::gen_range(5, 10) as f32;
+    pub fn new(config: &SimConfig, rng: &mut StdRng) -> Self {
+        let s = rng.gen_range(config.source_size_min..config.source_size_max).floor();
         Self {
-            pos: random_position(WORLD_W, WORLD_H),
-            rot: random_rotation(),
+            key: rng.gen::<u64>(),
+            kind: SourceKind::Plant,
+            pos: random_position(config.world_width, config.world_height, rng),
+            rot: random_rotation(rng),
             size: s,
-            max_eng: s.powi(2)*10.0,
-            eng: s.powi(2)*10.0,
+            max_eng: s.powi(2) * config.source_eng_capacity,
+            eng: s.powi(2) * config.source_eng_capacity,
             color: YELLOW,
             shape: Ball { radius: s },
             alife: true,
+            decay: 0.0,
+            seed_ready: false,
+            seed_timer: Timer::new(config.source_seed_interval, true, true, rng.gen_range(0.0..config.source_seed_interval)),
+            physics_handle: None,
         }
     }
-    pub fn draw(&self) {
-        let x0 = self.pos.x;
-        let y0 = self.pos.y;
-        draw_circle(x0, y0, self.size, self.color);
+
+    pub fn new_corpse(pos: Vec2, size: f32, eng: f32, config: &SimConfig, rng: &mut StdRng) -> Self {
+        let mut corpse = Source::new(config, rng);
+        corpse.kind = SourceKind::Corpse;
+        corpse.pos = pos;
+        corpse.size = size;
+        corpse.shape = Ball { radius: size };
+        corpse.max_eng = eng;
+        corpse.eng = eng;
+        corpse.color = MAROON;
+        corpse.decay = config.corpse_decay_rate;
+        return corpse;
+    }
+
+    //  the parent only pays once the seedling is accepted, see `commit_seed`
+    pub fn spawn_seedling(&self, config: &SimConfig, rng: &mut StdRng) -> Source {
+        let mut seedling = Source::new(config, rng);
+        let min_range = self.size + seedling.size;
+        let offset = random_unit_vec2(rng) * rng.gen_range(min_range..=config.source_seed_range.max(min_range));
+        let pos = self.pos + offset;
+        let bounds = WorldBounds::new(Vec2::new(config.world_width, config.world_height), config.boundary);
+        seedling.pos = match bounds.confine(pos, Vec2::ZERO) {
+            Some((pos, _)) => pos,
+            None => pos,
+        };
+        seedling.eng = self.seed_cost(config).min(seedling.max_eng);
+        return seedling;
+    }
+
+    pub fn seed_cost(&self, config: &SimConfig) -> f32 {
+        return self.max_eng * config.source_seed_cost;
+    }
+
+    pub fn commit_seed(&mut self, cost: f32) {
+        self.eng -= cost;
+        self.seed_ready = false;
+    }
+
+    pub fn draw(&self, offset: Vec2) {
+        let x0 = self.pos.x + offset.x;
+        let y0 = self.pos.y + offset.y;
+        let eng_ratio = (self.eng / self.max_eng).clamp(0.0, 1.0);
+        draw_circle_lines(x0, y0, self.size, 1.0, self.color);
+        draw_circle(x0, y0, self.size * eng_ratio.sqrt(), self.color);
+    }
+
+    pub fn growth_demand(&self, growth_rate: f32, dt: f32) -> f32 {
+        if self.kind != SourceKind::Plant {
+            return 0.0;
+        }
+        return (growth_rate * dt).min(self.max_eng - self.eng).max(0.0);
     }
-    pub fn update(&mut self, _dt: f32){
-        self.pos = wrap_around(&self.pos);
-        if self.eng <= 0.0 {
+
+    pub fn update(&mut self, dt: f32, growth: f32, seed_interval: f32, seed_min_eng: f32, physics: &mut World, bounds: &WorldBounds) {
+        if let Some((pos, _)) = bounds.confine(self.pos, Vec2::ZERO) {
+            self.pos = pos;
+            if let Some(handle) = self.physics_handle {
+                physics.set_body_state(handle, self.pos, self.rot, Vec2::ZERO, 0.0);
+            }
+        }
+        self.eng = (self.eng + growth - self.decay * dt).min(self.max_eng);
+        self.seed_timer.set_duration(seed_interval);
+        if self.seed_timer.update(dt) && self.kind == SourceKind::Plant && self.eng >= self.max_eng * seed_min_eng {
+            self.seed_ready = true;
+        }
+        if self.eng <= 0.0 && growth <= 0.0 {
             self.eng = 0.0;
             self.alife = false;
         }
     }
 
-    pub fn drain_eng(&mut self, eng_loss: f32) {
-        self.eng -= eng_loss;
+    pub fn drain_eng(&mut self, eng_loss: f32) -> f32 {
+        let eng = eng_loss.min(self.eng).max(0.0);
+        self.eng -= eng;
+        return eng;
     }
     
     pub fn update_collision(&mut self, collision_normal: &Vec2, penetration: f32, dt: f32) {
@@ -63,25 +145,27 @@ impl Source {
 
 
 pub struct SourcesBox {
-    pub sources: HashMap<u64, Source>
+    pub sources: BTreeMap<u64, Source>
 }
 
 impl SourcesBox {
     pub fn new() -> Self {
         Self {
-            sources: HashMap::new(),
+            sources: BTreeMap::new(),
         }
     }
 
-    pub fn add_many(&mut self, source_num: usize) {
+    pub fn add_many(&mut self, source_num: usize, physics_world: &mut World, config: &SimConfig, rng: &mut StdRng) {
         for _ in 0..source_num {
-            let source = Source::new();
-            _ = self.add_source(source);
+            let source = Source::new(config, rng);
+            _ = self.add_source(source, physics_world);
         }
     }
 
-    pub fn add_source(&mut self, source: Source) -> u64 {
-        let key: u64 = thread_rng().gen::<u64>();
+    pub fn add_source(&mut self, mut source: Source, physics_world: &mut World) -> u64 {
+        let key = source.key;
+        let handle = physics_world.add_fixed_circle_body(key, &source.pos, source.size);
+        source.physics_handle = Some(handle);
         self.sources.insert(key, source);
         return key;
     }
@@ -105,4 +189,8 @@ impl SourcesBox {
     pub fn count(&self) -> usize {
         return self.sources.len();
     }
+
+    pub fn count_kind(&self, kind: SourceKind) -> usize {
+        return self.sources.values().filter(|source| source.kind == kind).count();
+    }
 }
@@ -0,0 +1,43 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::sim::SimState;
+use crate::util::file_stem;
+
+
+const STATS_HEADER: &str = "sim_time,agents,sources,corpses,asteroids,physics_objects";
+
+pub struct StatsWriter {
+    pub path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl StatsWriter {
+    //  a fresh run starts the file over, a resumed one continues its series
+    pub fn open(dir: &Path, sim_name: &str, seed: u64, resume: bool) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("{}_{}.csv", file_stem(sim_name, "simulation"), seed));
+        let is_new = !resume || !path.exists();
+        let file = OpenOptions::new().create(true).write(true).append(resume).truncate(!resume).open(&path)
+            .map_err(|e| format!("can't open {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        if is_new {
+            writeln!(writer, "{}", STATS_HEADER).map_err(|e| format!("can't write {}: {}", path.display(), e))?;
+        }
+        return Ok(Self { path, writer });
+    }
+
+    pub fn record(&mut self, state: &SimState) -> Result<(), String> {
+        writeln!(
+            self.writer,
+            "{:.3},{},{},{},{},{}",
+            state.sim_time, state.agents_num, state.sources_num, state.corpses_num, state.asteroids_num, state.physics_num
+        ).map_err(|e| format!("can't write {}: {}", self.path.display(), e))?;
+        return Ok(());
+    }
+
+    pub fn flush(&mut self) {
+        _ = self.writer.flush();
+    }
+}
@@ -1,5 +1,3 @@
-//use macroquad::rand::*;
-use rand::{Rng, thread_rng};
 
 pub struct Timer {
     pub time: f32,
@@ -9,12 +7,7 @@ pub struct Timer {
 }
 
 impl Timer {
-    pub fn new(duration: f32, repeat: bool, autostart: bool, random_start: bool) -> Self {
-        let mut start_time: f32 = 0.0;
-        if random_start {
-            start_time = thread_rng().gen_range(0.0..duration);
-            //start_time = gen_range::<f32>(0.0, duration);
-        }
+    pub fn new(duration: f32, repeat: bool, autostart: bool, start_time: f32) -> Self {
         Self {
             time: start_time,
             duration,
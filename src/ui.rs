@@ -1,4 +1,5 @@
 //use std::path::Path;
+use std::ops::RangeInclusive;
 
 use egui::{self, Context};
 use egui::{Color32, RichText};
@@ -9,15 +10,22 @@ use macroquad::prelude::*;
 //use macroquad::ui::StyleBuilder;
 
 use crate::agent::Agent;
-use crate::consts::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::config::*;
+use crate::neuro::Activation;
+use crate::editor::*;
+use crate::map::list_maps;
+use crate::consts::{SCREEN_HEIGHT, SCREEN_WIDTH, WORLD_SIZE_MAX, WORLD_SIZE_MIN};
 use crate::sim::*;
+use crate::snapshot::{list_autosaves, list_saves};
 use crate::{progress_bar::*, Signals};
 
 
 pub struct UISystem {
     pub state: UIState,
     pub pointer_over: bool,
+    pub keyboard_over: bool,
     temp_sim_name: String,
+    config_editor: Option<ConfigEditor>,
 }
 
 impl UISystem {
@@ -25,41 +33,42 @@ impl UISystem {
         Self {
             state: UIState::new(),
             pointer_over: false,
+            keyboard_over: false,
             temp_sim_name: String::new(),
+            config_editor: None,
         }
     }
 
     pub fn ui_process(
         &mut self,
         sim_state: &SimState,
+        config: &SimConfig,
+        running: bool,
+        speed: SimSpeed,
         agent: Option<&Agent>,
         signals: &mut Signals,
         camera2d: &Camera2D,
+        editor: &mut MapEditor,
     ) {
         egui_macroquad::ui(|egui_ctx| {
             self.pointer_over = egui_ctx.is_pointer_over_area();
-            self.build_top_menu(egui_ctx, &sim_state.sim_name);
-            self.build_quit_window(egui_ctx);
-            self.build_monit_window(
-                egui_ctx,
-                sim_state.fps,
-                sim_state.dt,
-                sim_state.sim_time,
-                sim_state.agents_num,
-                sim_state.physics_num,
-                sim_state.asteroids_num,
-            );
+            self.keyboard_over = egui_ctx.wants_keyboard_input();
+            self.build_top_menu(egui_ctx, &sim_state.sim_name, running, speed, signals);
+            self.build_quit_window(egui_ctx, signals);
+            self.build_monit_window(egui_ctx, sim_state);
             self.build_debug_window(egui_ctx, camera2d);
             match agent {
                 Some(agent) => self.build_inspect_window(egui_ctx, agent),
                 None => {}
             }
             self.build_create_window(egui_ctx, signals);
-            self.build_new_sim_window(egui_ctx, signals);
+            self.build_new_sim_window(egui_ctx, config, signals);
+            self.build_load_sim_window(egui_ctx, signals);
+            self.build_editor_window(egui_ctx, editor, signals);
         });
     }
 
-    fn build_top_menu(&mut self, egui_ctx: &Context, sim_name: &str) {
+    fn build_top_menu(&mut self, egui_ctx: &Context, sim_name: &str, running: bool, speed: SimSpeed, signals: &mut Signals) {
         egui::TopBottomPanel::top("top_panel").default_height(100.0).show(egui_ctx, |ui| {
             if !self.pointer_over {
                 self.pointer_over = ui.ui_contains_pointer();
@@ -83,19 +92,23 @@ impl UISystem {
                     if ui
                         .button(
                             RichText::new("Load Simulation")
-                                .weak()
-                                .color(Color32::from_gray(100)),
+                                .strong()
+                                .color(Color32::WHITE),
                         )
                         .clicked()
-                    {}
+                    {
+                        self.state.load_sim = true;
+                    }
                     if ui
                         .button(
                             RichText::new("Save Simulation")
-                                .weak()
-                                .color(Color32::from_gray(100)),
+                                .strong()
+                                .color(Color32::WHITE),
                         )
                         .clicked()
-                    {}
+                    {
+                        signals.save_sim = true;
+                    }
                     if ui
                         .button(RichText::new("Quit").color(Color32::RED).strong())
                         .clicked()
@@ -147,6 +160,17 @@ impl UISystem {
                     {
                         self.state.create = !self.state.create;
                     }
+                    if ui
+                        .button(
+                            RichText::new("Map Editor")
+                                .strong()
+                                .color(Color32::WHITE),
+                        )
+                        .on_hover_text("E")
+                        .clicked()
+                    {
+                        self.state.editor = !self.state.editor;
+                    }
                 });
                 ui.add_space(10.0);
                 ui.separator();
@@ -173,21 +197,37 @@ impl UISystem {
                         self.state.docs = !self.state.docs;
                     }
                 });
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+                let (pause_label, pause_color) = if running { ("PAUSE", Color32::YELLOW) } else { ("RESUME", Color32::GREEN) };
+                if ui.button(RichText::new(pause_label).strong().color(pause_color)).on_hover_text("Space").clicked() {
+                    signals.pause_sim = true;
+                }
+                if ui.add_enabled(!running, egui::Button::new(RichText::new("STEP").strong())).on_hover_text(".").clicked() {
+                    signals.step_sim = true;
+                }
+                ui.add_space(5.0);
+                for (i, sim_speed) in SimSpeed::ALL.iter().enumerate() {
+                    let label = RichText::new(sim_speed.label()).strong();
+                    if ui.selectable_label(*sim_speed == speed, label).on_hover_text(format!("{}", i + 1)).clicked() {
+                        signals.sim_speed = Some(*sim_speed);
+                    }
+                }
             });
         });
     }
 
-    fn build_monit_window(
-        &self,
-        egui_ctx: &Context,
-        fps: i32,
-        delta: f32,
-        time: f64,
-        agents_num: i32,
-        physics_num: i32,
-        asteroids_num: usize,
-    ) {
+    fn build_monit_window(&self, egui_ctx: &Context, sim_state: &SimState) {
         if self.state.performance {
+            let fps = sim_state.fps;
+            let delta = sim_state.dt;
+            let time = sim_state.sim_time;
+            let agents_num = sim_state.agents_num;
+            let sources_num = sim_state.sources_num;
+            let corpses_num = sim_state.corpses_num;
+            let physics_num = sim_state.physics_num;
+            let asteroids_num = sim_state.asteroids_num;
             egui::Window::new("MONITOR")
                 .default_pos((5.0, 5.0))
                 .default_width(125.0)
@@ -200,9 +240,17 @@ impl UISystem {
                     ui.separator();
                     ui.label(format!("AGENTS: {}", agents_num));
                     ui.separator();
+                    ui.label(format!("SOURCES: {}", sources_num));
+                    ui.separator();
+                    ui.label(format!("CORPSES: {}", corpses_num));
+                    ui.separator();
                     ui.label(format!("ASTEROIDS: {}", asteroids_num));
                     ui.separator();
                     ui.label(format!("PHYSICS OBJECTS: {}", physics_num));
+                    if !sim_state.config_notice.is_empty() {
+                        ui.separator();
+                        ui.label(RichText::new(&sim_state.config_notice).small().color(Color32::YELLOW));
+                    }
                 });
         }
     }
@@ -237,6 +285,7 @@ impl UISystem {
                             ui.label(format!("angle to enemy: ---"));
                         }
                     }
+                    ui.label(format!("attacking: {}", agent.attacking));
                     ui.separator();
                     ui.label(format!(
                         "ENERGY: {}/{}",
@@ -273,7 +322,7 @@ impl UISystem {
         }
     }
 
-    fn build_quit_window(&mut self, egui_ctx: &Context) {
+    fn build_quit_window(&mut self, egui_ctx: &Context, signals: &mut Signals) {
         if self.state.quit {
             egui::Window::new("QUIT")
                 .default_pos((SCREEN_WIDTH / 2.0 - 65.0, SCREEN_HEIGHT / 4.0))
@@ -294,7 +343,8 @@ impl UISystem {
                                 .button(RichText::new("Yes").color(Color32::RED))
                                 .clicked()
                             {
-                                std::process::exit(0);
+                                self.state.quit = false;
+                                signals.quit = true;
                             }
                         });
                     });
@@ -302,12 +352,14 @@ impl UISystem {
         }
     }
 
-    fn build_new_sim_window(&mut self, egui_ctx: &Context, signals: &mut Signals) {
+    fn build_new_sim_window(&mut self, egui_ctx: &Context, config: &SimConfig, signals: &mut Signals) {
         if self.state.new_sim {
-            //let mut sim_name: String = String::new();
+            let editor = self.config_editor.get_or_insert_with(|| ConfigEditor::new(config.clone()));
+            let mut accept = false;
+            let mut cancel = false;
             egui::Window::new("NEW SIMULATION")
-                .default_pos((SCREEN_WIDTH / 2.0 - 65.0, SCREEN_HEIGHT / 4.0))
-                .default_width(125.0)
+                .default_pos((SCREEN_WIDTH / 2.0 - 150.0, SCREEN_HEIGHT / 8.0))
+                .default_width(300.0)
                 .show(egui_ctx, |ui| {
                     ui.horizontal(|head| {
                         head.heading("Start new simulation?");
@@ -318,40 +370,160 @@ impl UISystem {
                         if response.gained_focus() {
                             self.temp_sim_name = String::new();
                         }
-                        if response.changed() {
-                            //self.temp_sim_name = String::from(&sim_name);
-                            //println!("{:?}", sim_name);
-                            //println!("{:?}", self.temp_sim_name);
-                        }
                         if response.lost_focus() && txt.input(|i| i.key_pressed(egui::Key::Enter)) {
-                            self.state.new_sim = false;
-                            signals.new_sim = true;
-                            signals.new_sim_name = String::from(&self.temp_sim_name);
-                            self.temp_sim_name = String::new();
+                            accept = true;
                         }
-                        //let response = txt.text_edit_singleline(&mut sim_name);
                     });
+                    ui.separator();
+                    editor.build(ui);
+                    ui.separator();
+                    let errors = editor.errors();
+                    if let Some(errors) = errors.as_ref() {
+                        ui.label(RichText::new(errors).small().color(Color32::RED));
+                    }
                     ui.horizontal(|mid| {
                         mid.columns(2, |columns| {
                             if columns[0]
                                 .button(RichText::new("No").color(Color32::WHITE))
                                 .clicked()
                             {
-                                self.state.new_sim = false;
-                                self.temp_sim_name = String::new();
+                                cancel = true;
                             }
                             if columns[1]
-                                .button(RichText::new("Yes").color(Color32::RED))
+                                .add_enabled(errors.is_none(), egui::Button::new(RichText::new("Yes").color(Color32::RED)))
                                 .clicked()
                             {
-                                self.state.new_sim = false;
-                                signals.new_sim = true;
-                                signals.new_sim_name = String::from(&self.temp_sim_name);
-                                self.temp_sim_name = String::new();
+                                accept = true;
                             }
                         });
                     });
                 });
+            if accept && editor.errors().is_none() {
+                signals.new_sim = true;
+                signals.new_sim_name = String::from(&self.temp_sim_name);
+                signals.new_sim_config = Some(editor.config.clone());
+                cancel = true;
+            }
+            if cancel {
+                self.state.new_sim = false;
+                self.temp_sim_name = String::new();
+                self.config_editor = None;
+            }
+        }
+    }
+
+    fn build_load_sim_window(&mut self, egui_ctx: &Context, signals: &mut Signals) {
+        if !self.state.load_sim {
+            self.state.saves = None;
+            self.state.autosaves = None;
+        }
+        if self.state.load_sim {
+            let saves = self.state.saves.get_or_insert_with(list_saves).clone();
+            let autosaves = self.state.autosaves.get_or_insert_with(list_autosaves).clone();
+            egui::Window::new("LOAD SIMULATION")
+                .default_pos((SCREEN_WIDTH / 2.0 - 65.0, SCREEN_HEIGHT / 4.0))
+                .default_width(175.0)
+                .show(egui_ctx, |ui| {
+                    if saves.is_empty() {
+                        ui.label("no saved simulations");
+                    }
+                    for save in saves.iter() {
+                        if ui
+                            .button(RichText::new(save).strong().color(Color32::WHITE))
+                            .clicked()
+                        {
+                            self.state.load_sim = false;
+                            signals.load_sim = true;
+                            signals.load_sim_name = String::from(save);
+                        }
+                    }
+                    ui.separator();
+                    ui.label(RichText::new("RESUME FROM AUTOSAVE").strong());
+                    for autosave in autosaves.iter() {
+                        if ui
+                            .button(RichText::new(autosave).strong().color(Color32::LIGHT_BLUE))
+                            .clicked()
+                        {
+                            self.state.load_sim = false;
+                            signals.load_autosave = true;
+                            signals.load_sim_name = String::from(autosave);
+                        }
+                    }
+                    ui.separator();
+                    ui.horizontal(|row| {
+                        if row
+                            .button(RichText::new("Refresh").color(Color32::WHITE))
+                            .clicked()
+                        {
+                            self.state.saves = None;
+                            self.state.autosaves = None;
+                        }
+                        if row
+                            .button(RichText::new("Cancel").color(Color32::RED))
+                            .clicked()
+                        {
+                            self.state.load_sim = false;
+                        }
+                    });
+                });
+        }
+    }
+
+    fn build_editor_window(&mut self, egui_ctx: &Context, editor: &mut MapEditor, signals: &mut Signals) {
+        if !self.state.editor {
+            self.state.maps = None;
+        }
+        if self.state.editor {
+            let maps = self.state.maps.get_or_insert_with(list_maps).clone();
+            egui::Window::new("MAP EDITOR")
+                .default_pos((5.0, SCREEN_HEIGHT / 3.0))
+                .default_width(220.0)
+                .show(egui_ctx, |ui| {
+                    ui.horizontal_wrapped(|tools| {
+                        for tool in EditTool::ALL.iter() {
+                            if tools.selectable_label(editor.tool == *tool, RichText::new(tool.label()).strong()).clicked() {
+                                editor.set_tool(*tool);
+                            }
+                        }
+                    });
+                    ui.label(RichText::new(editor.tool.hint()).small());
+                    ui.separator();
+                    ui.horizontal(|row| {
+                        row.label("MAP:");
+                        row.text_edit_singleline(&mut editor.map_name);
+                    });
+                    ui.horizontal(|row| {
+                        if row.button(RichText::new("Save Map").strong().color(Color32::WHITE)).clicked() {
+                            signals.save_map = true;
+                            signals.map_name = String::from(&editor.map_name);
+                            self.state.maps = None;
+                        }
+                        if row.button(RichText::new("Clear Map").strong().color(Color32::RED)).clicked() {
+                            editor.cancel();
+                            signals.clear_map = true;
+                        }
+                    });
+                    ui.separator();
+                    ui.horizontal(|row| {
+                        row.label(RichText::new("LOAD MAP (starts a new simulation)").small());
+                        if row.small_button("Refresh").clicked() {
+                            self.state.maps = None;
+                        }
+                    });
+                    for map in maps.iter() {
+                        if ui.button(RichText::new(map).strong().color(Color32::WHITE)).clicked() {
+                            editor.cancel();
+                            editor.map_name = String::from(map);
+                            signals.load_map = true;
+                            signals.map_name = String::from(map);
+                        }
+                    }
+                    ui.separator();
+                    if ui.button(RichText::new("Close").color(Color32::RED)).clicked() {
+                        editor.cancel();
+                        self.state.editor = false;
+                    }
+                });
         }
     }
 
@@ -383,6 +555,211 @@ impl UISystem {
     }
 }
 
+//?         [[[CONFIG_EDITOR]]]
+//  editable copy of a SimConfig for the New Simulation dialog
+pub struct ConfigEditor {
+    pub config: SimConfig,
+    preset: ConfigPreset,
+    brain: String,
+    seed: String,
+}
+
+impl ConfigEditor {
+    pub fn new(config: SimConfig) -> Self {
+        let mut editor = Self {
+            config,
+            preset: ConfigPreset::Default,
+            brain: String::new(),
+            seed: String::new(),
+        };
+        editor.sync_text();
+        return editor;
+    }
+
+    fn sync_text(&mut self) {
+        self.brain = self.config.agent_brain_hidden.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(", ");
+        self.seed = self.config.seed.to_string();
+    }
+
+    fn set_config(&mut self, config: SimConfig) {
+        self.config = config;
+        self.sync_text();
+    }
+
+    //  text fields are parsed into the config on every edit, errors are reported by errors()
+    fn parse_text(&mut self) {
+        let layers: Result<Vec<usize>, _> = self.brain.split(',')
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<usize>())
+            .collect();
+        if let Ok(layers) = layers {
+            self.config.agent_brain_hidden = layers;
+        }
+        if let Ok(seed) = self.seed.trim().parse::<u64>() {
+            self.config.seed = seed;
+        }
+    }
+
+    pub fn errors(&self) -> Option<String> {
+        let mut errors: Vec<String> = vec![];
+        let layers_ok = self.brain.split(',')
+            .map(|n| n.trim())
+            .filter(|n| !n.is_empty())
+            .all(|n| n.parse::<usize>().is_ok());
+        if !layers_ok {
+            errors.push(String::from("`agent_brain_hidden`: expected comma separated layer sizes"));
+        }
+        if self.seed.trim().parse::<u64>().is_err() {
+            errors.push(String::from("`seed`: expected a whole number"));
+        }
+        if let Err(e) = self.config.validate() {
+            errors.push(e);
+        }
+        if errors.is_empty() {
+            return None;
+        }
+        return Some(errors.join("; "));
+    }
+
+    pub fn build(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|row| {
+            row.label("PRESET:");
+            egui::ComboBox::from_id_source("config_preset")
+                .selected_text(self.preset.label())
+                .show_ui(row, |combo| {
+                    for preset in ConfigPreset::ALL.iter() {
+                        if combo.selectable_value(&mut self.preset, *preset, preset.label()).clicked() {
+                            self.set_config(preset.config());
+                        }
+                    }
+                });
+            if row.button("Reset to defaults").clicked() {
+                self.preset = ConfigPreset::Default;
+                self.set_config(SimConfig::default());
+            }
+        });
+        egui::ScrollArea::vertical().max_height(SCREEN_HEIGHT / 2.0).show(ui, |ui| {
+            let c = &mut self.config;
+            egui::CollapsingHeader::new("WORLD").default_open(true).show(ui, |ui| {
+                egui::Grid::new("config_world").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "width", &mut c.world_width, WORLD_SIZE_MIN..=WORLD_SIZE_MAX, 10.0);
+                    config_value(grid, "height", &mut c.world_height, WORLD_SIZE_MIN..=WORLD_SIZE_MAX, 10.0);
+                    grid.label("boundary");
+                    egui::ComboBox::from_id_source("config_boundary")
+                        .selected_text(c.boundary.label())
+                        .show_ui(grid, |combo| {
+                            for mode in BoundaryMode::ALL.iter() {
+                                combo.selectable_value(&mut c.boundary, *mode, mode.label());
+                            }
+                        });
+                    grid.end_row();
+                    grid.label("seed (0 = random)");
+                    grid.text_edit_singleline(&mut self.seed);
+                    grid.end_row();
+                });
+            });
+            egui::CollapsingHeader::new("AGENTS").default_open(true).show(ui, |ui| {
+                egui::Grid::new("config_agents").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "initial number", &mut c.agents_init_num, 0..=1000, 1.0);
+                    config_value(grid, "minimum number", &mut c.agent_min_num, 0..=1000, 1.0);
+                    config_value(grid, "size min", &mut c.agent_size_min, 1.0..=50.0, 0.1);
+                    config_value(grid, "size max", &mut c.agent_size_max, 1.0..=50.0, 0.1);
+                    config_value(grid, "speed", &mut c.agent_speed, 1.0..=500.0, 1.0);
+                    config_value(grid, "rotation", &mut c.agent_rotation, 0.1..=20.0, 0.1);
+                    config_value(grid, "vision range", &mut c.agent_vision_range, 10.0..=2000.0, 5.0);
+                    config_value(grid, "analyze interval", &mut c.agent_analyze_interval, 0.01..=5.0, 0.01);
+                    config_value(grid, "energy capacity", &mut c.agent_eng_capacity, 0.1..=1000.0, 0.1);
+                    config_value(grid, "metabolism", &mut c.agent_metabolism, 0.0..=100.0, 0.01);
+                    grid.label("hidden layers");
+                    grid.text_edit_singleline(&mut self.brain);
+                    grid.end_row();
+                    activation_value(grid, "hidden activation", &mut c.agent_brain_hidden_activation);
+                    activation_value(grid, "output activation", &mut c.agent_brain_output_activation);
+                    config_value(grid, "eat rate", &mut c.agent_eat_rate, 0.0..=1000.0, 1.0);
+                    config_value(grid, "attack damage", &mut c.attack_damage, 0.0..=1000.0, 1.0);
+                    config_value(grid, "attack efficiency", &mut c.attack_efficiency, 0.0..=1.0, 0.01);
+                    config_value(grid, "attack cost", &mut c.attack_cost, 0.0..=100.0, 0.1);
+                });
+            });
+            egui::CollapsingHeader::new("REPRODUCTION").show(ui, |ui| {
+                egui::Grid::new("config_repro").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "energy threshold", &mut c.repro_eng_threshold, 0.0..=1.0, 0.01);
+                    config_value(grid, "energy cost", &mut c.repro_eng_cost, 0.0..=1.0, 0.01);
+                    config_value(grid, "mutation rate", &mut c.mutation_rate, 0.0..=1.0, 0.01);
+                    config_value(grid, "mutation strength", &mut c.mutation_strength, 0.0..=5.0, 0.01);
+                    config_value(grid, "mate min energy", &mut c.mate_min_eng, 0.0..=1.0, 0.01);
+                    config_value(grid, "mate energy cost", &mut c.mate_eng_cost, 0.0..=1.0, 0.01);
+                    config_value(grid, "mate cooldown", &mut c.mate_cooldown, 0.0..=300.0, 1.0);
+                    config_value(grid, "mate max distance", &mut c.mate_max_distance, 0.0..=10.0, 0.01);
+                });
+            });
+            egui::CollapsingHeader::new("SOURCES").show(ui, |ui| {
+                egui::Grid::new("config_sources").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "initial number", &mut c.sources_init_num, 0..=4096, 1.0);
+                    config_value(grid, "minimum number", &mut c.sources_min_num, 0..=4096, 1.0);
+                    config_value(grid, "maximum number", &mut c.sources_max_num, 0..=4096, 1.0);
+                    config_value(grid, "size min", &mut c.source_size_min, 1.0..=50.0, 0.1);
+                    config_value(grid, "size max", &mut c.source_size_max, 1.0..=50.0, 0.1);
+                    config_value(grid, "energy capacity", &mut c.source_eng_capacity, 0.1..=1000.0, 0.1);
+                    config_value(grid, "growth rate", &mut c.source_growth_rate, 0.0..=100.0, 0.1);
+                    config_value(grid, "seed interval", &mut c.source_seed_interval, 0.1..=600.0, 1.0);
+                    config_value(grid, "seed range", &mut c.source_seed_range, 0.0..=1000.0, 1.0);
+                    config_value(grid, "seed cost", &mut c.source_seed_cost, 0.0..=1.0, 0.01);
+                    config_value(grid, "seed min energy", &mut c.source_seed_min_eng, 0.0..=1.0, 0.01);
+                    grid.label("nutrients");
+                    grid.checkbox(&mut c.nutrients, "");
+                    grid.end_row();
+                    config_value(grid, "nutrient cell size", &mut c.nutrient_cell_size, 10.0..=1000.0, 5.0);
+                    config_value(grid, "nutrient max", &mut c.nutrient_max, 0.0..=100000.0, 10.0);
+                    config_value(grid, "nutrient regen", &mut c.nutrient_regen, 0.0..=1000.0, 0.1);
+                    config_value(grid, "corpse energy ratio", &mut c.corpse_eng_ratio, 0.0..=1.0, 0.01);
+                    config_value(grid, "corpse decay rate", &mut c.corpse_decay_rate, 0.0..=100.0, 0.1);
+                });
+            });
+            egui::CollapsingHeader::new("ASTEROIDS").show(ui, |ui| {
+                egui::Grid::new("config_asteroids").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "number", &mut c.asteroids_num, 0..=20000, 10.0);
+                    config_value(grid, "size min", &mut c.asteroid_size_min, 1.0..=100.0, 0.1);
+                    config_value(grid, "size max", &mut c.asteroid_size_max, 1.0..=100.0, 0.1);
+                    config_value(grid, "speed", &mut c.asteroid_speed, 0.0..=1000.0, 1.0);
+                    config_value(grid, "impact damage", &mut c.impact_damage, 0.0..=1.0, 0.0001);
+                });
+            });
+            egui::CollapsingHeader::new("AUTOSAVE").show(ui, |ui| {
+                egui::Grid::new("config_autosave").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "interval", &mut c.autosave_interval, 0.0..=3600.0, 10.0);
+                    config_value(grid, "keep", &mut c.autosave_keep, 1..=100, 1.0);
+                });
+            });
+            egui::CollapsingHeader::new("STATS").show(ui, |ui| {
+                egui::Grid::new("config_stats").num_columns(2).show(ui, |grid| {
+                    config_value(grid, "interval", &mut c.stats_interval, 0.1..=3600.0, 1.0);
+                });
+            });
+        });
+        self.parse_text();
+    }
+}
+
+fn config_value<T: egui::emath::Numeric>(grid: &mut egui::Ui, label: &str, value: &mut T, range: RangeInclusive<T>, speed: f64) {
+    grid.label(label);
+    grid.add(egui::DragValue::new(value).clamp_range(range).speed(speed));
+    grid.end_row();
+}
+
+fn activation_value(grid: &mut egui::Ui, label: &str, value: &mut Activation) {
+    grid.label(label);
+    egui::ComboBox::from_id_source(label)
+        .selected_text(value.label())
+        .show_ui(grid, |combo| {
+            for activation in Activation::ALL.iter() {
+                combo.selectable_value(value, *activation, activation.label());
+            }
+        });
+    grid.end_row();
+}
+
 //?         [[[UISTATE]]]
 pub struct UIState {
     pub new_sim_name: String,
@@ -393,8 +770,14 @@ pub struct UIState {
     pub quit: bool,
     pub agents_num: i32,
     pub new_sim: bool,
+    pub load_sim: bool,
     pub credits: bool,
     pub docs: bool,
+    pub editor: bool,
+    //  directory listings, read when their window opens or on Refresh instead of every frame
+    pub saves: Option<Vec<String>>,
+    pub autosaves: Option<Vec<String>>,
+    pub maps: Option<Vec<String>>,
 }
 
 impl UIState {
@@ -408,8 +791,13 @@ impl UIState {
             quit: false,
             agents_num: 0,
             new_sim: false,
+            load_sim: false,
             credits: false,
             docs: false,
+            editor: false,
+            saves: None,
+            autosaves: None,
+            maps: None,
         }
     }
 }
@@ -3,32 +3,35 @@
 use std::f32::consts::PI;
 
 use macroquad::{prelude::*, color};
+use ::rand::{Rng, rngs::StdRng};
 use nalgebra::*;
 use parry2d::math::Real;
 use crate::consts::*;
+use crate::config::{BoundaryMode, SimConfig};
+use crate::sim::SimSpeed;
 
 
-pub fn random_position(x_max: f32, y_max: f32) -> Vec2 {
-    let x = rand::gen_range(0.0, x_max);
-    let y = rand::gen_range(0.0, y_max);
+pub fn random_position(x_max: f32, y_max: f32, rng: &mut StdRng) -> Vec2 {
+    let x = rng.gen_range(0.0..x_max);
+    let y = rng.gen_range(0.0..y_max);
     return  Vec2::new(x, y);
 }
 
-pub fn random_rotation() -> f32 {
-    let rot = rand::gen_range(0.0, PI*2.0);
+pub fn random_rotation(rng: &mut StdRng) -> f32 {
+    let rot = rng.gen_range(0.0..PI*2.0);
     return rot;
 }
 
-pub fn random_unit_vec2() -> Vec2 {
-    let x = rand::gen_range(-1.0, 1.0);
-    let y = rand::gen_range(-1.0, 1.0);
+pub fn random_unit_vec2(rng: &mut StdRng) -> Vec2 {
+    let x = rng.gen_range(-1.0..1.0);
+    let y = rng.gen_range(-1.0..1.0);
     return  Vec2::new(x, y).normalize_or_zero();    
 }
 
-pub fn random_color() -> color::Color {
+pub fn random_color(rng: &mut StdRng) -> color::Color {
     let colors = vec![RED, GREEN, BLUE, YELLOW, ORANGE, GRAY, SKYBLUE, LIME];
     let num = colors.len();
-    let c = rand::gen_range(0, num);
+    let c = rng.gen_range(0..num);
     return  colors[c];
 }
 
@@ -38,20 +41,123 @@ pub fn angle2vec2(angle: f32) -> Vec2 {
     return v;
 }
 
-pub fn wrap_around(v: &Vec2) -> Vec2 {
-    let tolerance = 5.0;
-    let mut vr = Vec2::new(v.x, v.y);
-    if vr.x > WORLD_W+tolerance {
-        vr.x = 0.0-tolerance;
-    } else if vr.x < 0.0-tolerance {
-        vr.x = WORLD_W+tolerance;
+//?         [[[WORLD_BOUNDS]]]
+#[derive(Clone, Copy)]
+pub struct WorldBounds {
+    pub size: Vec2,
+    pub mode: BoundaryMode,
+}
+
+impl WorldBounds {
+    pub fn new(size: Vec2, mode: BoundaryMode) -> Self {
+        Self { size, mode }
+    }
+
+    pub fn contains(&self, pos: Vec2) -> bool {
+        return pos.x >= 0.0 && pos.y >= 0.0 && pos.x <= self.size.x && pos.y <= self.size.y;
+    }
+
+    pub fn wraps(&self) -> bool {
+        return self.mode == BoundaryMode::Wrap;
+    }
+
+    //  shortest displacement from `from` to `to`, across the seam when the world wraps
+    pub fn delta(&self, from: Vec2, to: Vec2) -> Vec2 {
+        let mut d = to - from;
+        if self.wraps() {
+            d.x -= (d.x / self.size.x).round() * self.size.x;
+            d.y -= (d.y / self.size.y).round() * self.size.y;
+        }
+        return d;
+    }
+
+    pub fn distance(&self, a: Vec2, b: Vec2) -> f32 {
+        return self.delta(a, b).length();
+    }
+
+    //  offsets of the copies of something within `margin` of a seam, empty unless the world wraps
+    pub fn ghost_offsets(&self, pos: Vec2, margin: f32) -> Vec<Vec2> {
+        let mut offsets: Vec<Vec2> = vec![];
+        if !self.wraps() {
+            return offsets;
+        }
+        let mut xs = vec![0.0];
+        let mut ys = vec![0.0];
+        if pos.x < margin {
+            xs.push(self.size.x);
+        } else if pos.x > self.size.x - margin {
+            xs.push(-self.size.x);
+        }
+        if pos.y < margin {
+            ys.push(self.size.y);
+        } else if pos.y > self.size.y - margin {
+            ys.push(-self.size.y);
+        }
+        for x in xs.iter() {
+            for y in ys.iter() {
+                if *x != 0.0 || *y != 0.0 {
+                    offsets.push(Vec2::new(*x, *y));
+                }
+            }
+        }
+        return offsets;
+    }
+
+    //  new position and velocity for something that left the world, None while it's inside
+    pub fn confine(&self, pos: Vec2, vel: Vec2) -> Option<(Vec2, Vec2)> {
+        if self.contains(pos) {
+            return None;
+        }
+        let mut p = pos;
+        let mut v = vel;
+        match self.mode {
+            BoundaryMode::Wrap => {
+                p = Vec2::new(pos.x.rem_euclid(self.size.x), pos.y.rem_euclid(self.size.y));
+            },
+            BoundaryMode::Walls => {
+                //  wall colliders do the stopping, this only catches whatever tunnels through
+                if (p.x < 0.0 && v.x < 0.0) || (p.x > self.size.x && v.x > 0.0) {
+                    v.x = 0.0;
+                }
+                if (p.y < 0.0 && v.y < 0.0) || (p.y > self.size.y && v.y > 0.0) {
+                    v.y = 0.0;
+                }
+                p = p.clamp(Vec2::ZERO, self.size);
+            },
+            BoundaryMode::Bounce => {
+                if p.x < 0.0 {
+                    p.x = -p.x;
+                    v.x = v.x.abs();
+                } else if p.x > self.size.x {
+                    p.x = 2.0 * self.size.x - p.x;
+                    v.x = -v.x.abs();
+                }
+                if p.y < 0.0 {
+                    p.y = -p.y;
+                    v.y = v.y.abs();
+                } else if p.y > self.size.y {
+                    p.y = 2.0 * self.size.y - p.y;
+                    v.y = -v.y.abs();
+                }
+                p = p.clamp(Vec2::ZERO, self.size);
+            },
+        }
+        return Some((p, v));
+    }
+}
+
+pub fn lerp_pose(prev_pos: Vec2, pos: Vec2, prev_rot: f32, rot: f32, alpha: f32) -> (Vec2, f32) {
+    //  teleports (edge wrapping, respawns) are drawn at the new pose instead of smeared across the world
+    if prev_pos.distance(pos) > LERP_MAX_JUMP {
+        return (pos, rot);
     }
-    if vr.y > WORLD_H+tolerance {
-        vr.y = 0.0-tolerance;
-    } else if vr.y < 0.0-tolerance {
-        vr.y = WORLD_H+tolerance;
+    let mut drot = (rot - prev_rot) % (2.0*PI);
+    if drot > PI {
+        drot -= 2.0*PI;
+    } else if drot < -PI {
+        drot += 2.0*PI;
     }
-    return vr;
+    return (prev_pos.lerp(pos, alpha), prev_rot + drot * alpha);
 }
 
 pub fn make_isometry(posx: f32, posy: f32, rotation: f32) -> nalgebra::Isometry2<f32> {
@@ -63,13 +169,13 @@ pub fn matric_to_vec2(translation: Translation<Real, 2>) -> Vec2 {
     return Vec2::new(translation.x, translation.y);
 }
 
-pub fn map_polygon(n: usize, r: f32, dev: f32) -> Vec<Vec2> {
+pub fn map_polygon(n: usize, r: f32, dev: f32, rng: &mut StdRng) -> Vec<Vec2> {
     let mut points: Vec<Vec2> = vec![];
     //let mut opoints: Vec<Point2<f32>> = vec![];
     let s = 2.0*PI/(n as f32);
     let mut a = 2.0*PI;
     for _ in 0..n {
-        let d = rand::gen_range(-dev, dev);
+        let d = rng.gen_range(-dev..dev);
         let step = s + s*d;
         a -= step;
         let x = a.sin();
@@ -97,11 +203,35 @@ pub fn vec2_to_point2_collection(vec2_list: &Vec<Vec2>) -> Vec<Point2<f32>> {
     return points;
 }
 
+//  user typed names become a single file or directory name: no separators, no `..`, never empty
+pub fn file_stem(name: &str, fallback: &str) -> String {
+    let stem: String = name.trim().chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+        .collect();
+    if stem.is_empty() {
+        return fallback.to_string();
+    }
+    return stem;
+}
+
 //?         [[[SIGNALS]]]
 pub struct Signals {
     pub spawn_agent: bool,
     pub new_sim: bool,
     pub new_sim_name: String,
+    pub new_sim_config: Option<SimConfig>,
+    pub save_sim: bool,
+    pub save_map: bool,
+    pub load_map: bool,
+    pub clear_map: bool,
+    pub map_name: String,
+    pub load_sim: bool,
+    pub load_sim_name: String,
+    pub load_autosave: bool,
+    pub quit: bool,
+    pub pause_sim: bool,
+    pub step_sim: bool,
+    pub sim_speed: Option<SimSpeed>,
 }
 
 impl Signals {
@@ -110,6 +240,83 @@ impl Signals {
             spawn_agent: false,
             new_sim: false,
             new_sim_name: String::new(),
+            new_sim_config: None,
+            save_sim: false,
+            save_map: false,
+            load_map: false,
+            clear_map: false,
+            map_name: String::new(),
+            load_sim: false,
+            load_sim_name: String::new(),
+            load_autosave: false,
+            quit: false,
+            pause_sim: false,
+            step_sim: false,
+            sim_speed: None,
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(mode: BoundaryMode) -> WorldBounds {
+        return WorldBounds::new(Vec2::new(100.0, 50.0), mode);
+    }
+
+    #[test]
+    fn confine_ignores_inside_positions() {
+        for mode in BoundaryMode::ALL {
+            assert!(bounds(mode).confine(Vec2::new(0.0, 50.0), Vec2::ONE).is_none());
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn confine_wraps_to_the_opposite_side() {
+        let (pos, vel) = bounds(BoundaryMode::Wrap).confine(Vec2::new(-5.0, 53.0), Vec2::new(-1.0, 2.0)).unwrap();
+        assert_eq!(pos, Vec2::new(95.0, 3.0));
+        assert_eq!(vel, Vec2::new(-1.0, 2.0));
+    }
+
+    #[test]
+    fn confine_bounce_reflects_position_and_velocity() {
+        let (pos, vel) = bounds(BoundaryMode::Bounce).confine(Vec2::new(104.0, -3.0), Vec2::new(2.0, -1.0)).unwrap();
+        assert_eq!(pos, Vec2::new(96.0, 3.0));
+        assert_eq!(vel, Vec2::new(-2.0, 1.0));
+    }
+
+    #[test]
+    fn confine_walls_clamp_and_stop_outward_motion() {
+        let (pos, vel) = bounds(BoundaryMode::Walls).confine(Vec2::new(-4.0, 60.0), Vec2::new(-3.0, -1.0)).unwrap();
+        assert_eq!(pos, Vec2::new(0.0, 50.0));
+        assert_eq!(vel, Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn delta_takes_the_nearest_image_across_both_seams() {
+        let wrap = bounds(BoundaryMode::Wrap);
+        assert_eq!(wrap.delta(Vec2::new(95.0, 2.0), Vec2::new(3.0, 48.0)), Vec2::new(8.0, -4.0));
+        assert_eq!(wrap.delta(Vec2::new(3.0, 48.0), Vec2::new(95.0, 2.0)), Vec2::new(-8.0, 4.0));
+        assert_eq!(wrap.delta(Vec2::new(10.0, 10.0), Vec2::new(40.0, 30.0)), Vec2::new(30.0, 20.0));
+        assert_eq!(wrap.distance(Vec2::new(1.0, 25.0), Vec2::new(99.0, 25.0)), 2.0);
+    }
+
+    #[test]
+    fn delta_is_plain_difference_without_wrap() {
+        let walls = bounds(BoundaryMode::Walls);
+        assert_eq!(walls.delta(Vec2::new(95.0, 2.0), Vec2::new(3.0, 48.0)), Vec2::new(-92.0, 46.0));
+    }
+
+    #[test]
+    fn ghost_offsets_cover_edges_and_corners() {
+        let wrap = bounds(BoundaryMode::Wrap);
+        assert!(wrap.ghost_offsets(Vec2::new(50.0, 25.0), 10.0).is_empty());
+        assert_eq!(wrap.ghost_offsets(Vec2::new(95.0, 25.0), 10.0), vec![Vec2::new(-100.0, 0.0)]);
+        let corner = wrap.ghost_offsets(Vec2::new(2.0, 45.0), 10.0);
+        assert_eq!(corner.len(), 3);
+        for offset in [Vec2::new(0.0, -50.0), Vec2::new(100.0, 0.0), Vec2::new(100.0, -50.0)] {
+            assert!(corner.contains(&offset));
+        }
+        assert!(bounds(BoundaryMode::Bounce).ghost_offsets(Vec2::new(2.0, 45.0), 10.0).is_empty());
+    }
+}
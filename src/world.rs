@@ -1,11 +1,10 @@
-//use crossbeam::channel::{Receiver, Sender};
-//use crossbeam::*;
+use crossbeam::channel::{Receiver};
 use macroquad::prelude::*;
 use nalgebra::{Point2};
 use rapier2d::{na::Vector2, prelude::*};
-use std::collections::{HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::f32::consts::PI;
-use crate::consts::ASTER_SPEED;
+use crate::consts::{FIX_DT, WALL_THICKNESS};
 use crate::util::*;
 
 
@@ -23,22 +22,30 @@ pub struct World {
     multibody_joint_set: MultibodyJointSet,
     ccd_solver: CCDSolver,
     physics_hooks: (),
-    event_handler: (),
-    //event_handler: ChannelEventCollector,
-    //collision_recv: Receiver<CollisionEvent>,
+    event_handler: ChannelEventCollector,
+    collision_recv: Receiver<CollisionEvent>,
+    contact_force_recv: Receiver<ContactForceEvent>,
+    active_contacts: BTreeSet<(u64, u64)>,
+    pub contacts: HashMap<u64, Vec<u64>>,
+    pub new_contacts: Vec<(u64, u64)>,
     pub detections: HashMap<RigidBodyHandle, (RigidBodyHandle, f32)>,
+    obstacles: HashSet<RigidBodyHandle>,
 }
 
 impl World {
     pub fn new() -> Self {
-        //let (collision_send, collision_recv) = crossbeam::channel::unbounded();
-        //let (contact_force_send, contact_force_recv) = crossbeam::channel::unbounded();
-        //let event_handler = ChannelEventCollector::new(collision_send, contact_force_send);
+        let (collision_send, collision_recv) = crossbeam::channel::unbounded();
+        let (contact_force_send, contact_force_recv) = crossbeam::channel::unbounded();
+        let event_handler = ChannelEventCollector::new(collision_send, contact_force_send);
+        let integration_parameters = IntegrationParameters {
+            dt: FIX_DT,
+            ..Default::default()
+        };
         Self {
             rigid_bodies: RigidBodySet::new(),
             colliders: ColliderSet::new(),
             gravity: Vector2::new(0.0, 0.0),
-            integration_parameters: IntegrationParameters::default(),
+            integration_parameters,
             physics_pipeline: PhysicsPipeline::new(),
             query_pipeline: QueryPipeline::new(),
             island_manager: IslandManager::new(),
@@ -48,10 +55,14 @@ impl World {
             multibody_joint_set: MultibodyJointSet::new(),
             ccd_solver: CCDSolver::new(),
             physics_hooks: (),
-            event_handler: (),
-            //event_handler: event_handler,
-            //collision_recv: collision_recv,
+            event_handler,
+            collision_recv,
+            contact_force_recv,
+            active_contacts: BTreeSet::new(),
+            contacts: HashMap::new(),
+            new_contacts: vec![],
             detections: HashMap::new(),
+            obstacles: HashSet::new(),
         }
     }
 
@@ -74,7 +85,78 @@ impl World {
         return rb_handle;
     }
 
-    pub fn add_poly_body(&mut self, key: u64, position: &Vec2, points: Vec<Point2<f32>>) -> RigidBodyHandle {
+    pub fn add_fixed_circle_body(&mut self, key: u64, position: &Vec2, radius: f32) -> RigidBodyHandle {
+        let iso = Isometry::new(Vector2::new(position.x, position.y), 0.0);
+        let ball = RigidBodyBuilder::fixed().position(iso).user_data(key as u128).build();
+        let collider = ColliderBuilder::ball(radius)
+            .active_collision_types(ActiveCollisionTypes::default())
+            .active_events(ActiveEvents::COLLISION_EVENTS).build();
+        let rb_handle = self.rigid_bodies.insert(ball);
+        _ = self.colliders.insert_with_parent(collider, rb_handle, &mut self.rigid_bodies);
+        return rb_handle;
+    }
+
+    //  four fixed slabs just outside the world rectangle, key 0 so they never match an entity
+    pub fn add_walls(&mut self, world_size: Vec2) -> RigidBodyHandle {
+        let t = WALL_THICKNESS;
+        let (w, h) = (world_size.x, world_size.y);
+        let body = RigidBodyBuilder::fixed().user_data(0).build();
+        let rb_handle = self.rigid_bodies.insert(body);
+        let slabs = [
+            (Vector2::new(-t / 2.0, h / 2.0), Vector2::new(t / 2.0, h / 2.0 + t)),
+            (Vector2::new(w + t / 2.0, h / 2.0), Vector2::new(t / 2.0, h / 2.0 + t)),
+            (Vector2::new(w / 2.0, -t / 2.0), Vector2::new(w / 2.0 + t, t / 2.0)),
+            (Vector2::new(w / 2.0, h + t / 2.0), Vector2::new(w / 2.0 + t, t / 2.0)),
+        ];
+        for (center, half) in slabs.iter() {
+            let collider = ColliderBuilder::cuboid(half.x, half.y).translation(*center).build();
+            _ = self.colliders.insert_with_parent(collider, rb_handle, &mut self.rigid_bodies);
+        }
+        return rb_handle;
+    }
+
+    pub fn add_fixed_polyline(&mut self, key: u64, points: &Vec<Vec2>) -> RigidBodyHandle {
+        let body = RigidBodyBuilder::fixed().user_data(key as u128).build();
+        let collider = ColliderBuilder::polyline(vec2_to_point2_collection(points), None).build();
+        let rb_handle = self.rigid_bodies.insert(body);
+        _ = self.colliders.insert_with_parent(collider, rb_handle, &mut self.rigid_bodies);
+        self.obstacles.insert(rb_handle);
+        return rb_handle;
+    }
+
+    pub fn add_fixed_convex(&mut self, key: u64, points: &Vec<Vec2>) -> Option<RigidBodyHandle> {
+        let collider = ColliderBuilder::convex_hull(&vec2_to_point2_collection(points))?.build();
+        let body = RigidBodyBuilder::fixed().user_data(key as u128).build();
+        let rb_handle = self.rigid_bodies.insert(body);
+        _ = self.colliders.insert_with_parent(collider, rb_handle, &mut self.rigid_bodies);
+        self.obstacles.insert(rb_handle);
+        return Some(rb_handle);
+    }
+
+    //  false when an obstacle lies on the segment between the two points
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        if self.obstacles.is_empty() {
+            return true;
+        }
+        let dir = to - from;
+        let dist = dir.length();
+        if dist <= f32::EPSILON {
+            return true;
+        }
+        let ray = Ray::new(point![from.x, from.y], vector![dir.x / dist, dir.y / dist]);
+        let is_obstacle = |_: ColliderHandle, collider: &Collider| -> bool {
+            return collider.parent().is_some_and(|rbh| self.obstacles.contains(&rbh));
+        };
+        let filter = QueryFilter {
+            flags: QueryFilterFlags::ONLY_FIXED | QueryFilterFlags::EXCLUDE_SENSORS,
+            predicate: Some(&is_obstacle),
+            ..Default::default()
+        };
+        let hit = self.query_pipeline.cast_ray(&self.rigid_bodies, &self.colliders, &ray, dist, true, filter);
+        return hit.is_none();
+    }
+
+    pub fn add_poly_body(&mut self, key: u64, position: &Vec2, points: Vec<Point2<f32>>, lin_vel: Vec2) -> RigidBodyHandle {
         let iso = Isometry::new(Vector2::new(position.x, position.y), 0.0);
         let poly = RigidBodyBuilder::dynamic().position(iso)
             .linear_damping(0.0).angular_damping(0.0)
@@ -87,7 +169,7 @@ impl World {
         let coll_handle = self.colliders.insert_with_parent(collider, rb_handle, &mut self.rigid_bodies);
         let obj = self.rigid_bodies.get_mut(rb_handle).unwrap();
         //obj.add
-        let imp = Vector2::new(rand::gen_range(-1.0, 1.0), rand::gen_range(-1.0, 1.0)) * ASTER_SPEED;
+        let imp = Vector2::new(lin_vel.x, lin_vel.y);
         //obj.apply_impulse(imp, true);
         obj.set_linvel(imp, true);
         return rb_handle;
@@ -123,6 +205,10 @@ impl World {
     } */
 
     pub fn remove_physics_object(&mut self, body_handle: RigidBodyHandle) {
+        self.obstacles.remove(&body_handle);
+        if let Some(key) = self.get_object_key(body_handle) {
+            self.active_contacts.retain(|(key1, key2)| *key1 != key && *key2 != key);
+        }
         _ = self.rigid_bodies.remove(
             body_handle,
             &mut self.island_manager,
@@ -154,6 +240,58 @@ impl World {
             &self.physics_hooks,
             &self.event_handler,
         );
+        self.collect_contacts();
+    }
+
+    fn get_collider_key(&self, collider_handle: ColliderHandle) -> Option<u64> {
+        match self.get_body_handle_from_collider(collider_handle) {
+            Some(rbh) => {
+                return self.get_object_key(rbh);
+            },
+            None => {
+                return None;
+            }
+        }
+    }
+
+    fn collect_contacts(&mut self) {
+        self.new_contacts.clear();
+        while let Ok(event) = self.collision_recv.try_recv() {
+            if event.sensor() {
+                continue;
+            }
+            let key1 = self.get_collider_key(event.collider1());
+            let key2 = self.get_collider_key(event.collider2());
+            match (key1, key2) {
+                (Some(key1), Some(key2)) => {
+                    let pair = if key1 < key2 { (key1, key2) } else { (key2, key1) };
+                    if event.started() {
+                        self.active_contacts.insert(pair);
+                        self.new_contacts.push(pair);
+                    } else {
+                        self.active_contacts.remove(&pair);
+                    }
+                },
+                (_, _) => {},
+            }
+        }
+        while self.contact_force_recv.try_recv().is_ok() {}
+        self.contacts.clear();
+        for (key1, key2) in self.active_contacts.iter() {
+            self.contacts.entry(*key1).or_default().push(*key2);
+            self.contacts.entry(*key2).or_default().push(*key1);
+        }
+    }
+
+    pub fn get_contacts(&self, key: u64) -> Vec<u64> {
+        match self.contacts.get(&key) {
+            Some(keys) => {
+                return keys.clone();
+            },
+            None => {
+                return vec![];
+            }
+        }
     }
 
     fn iso_to_vec2_rot(&self, isometry: &Isometry<Real>) -> (Vec2, f32) {
@@ -162,6 +300,17 @@ impl World {
         return (pos, rot);
     }
 
+    pub fn set_body_state(&mut self, handle: RigidBodyHandle, position: Vec2, rotation: f32, lin_vel: Vec2, ang_vel: f32) {
+        match self.rigid_bodies.get_mut(handle) {
+            Some(body) => {
+                body.set_position(Isometry::new(Vector2::new(position.x, position.y), rotation - PI), true);
+                body.set_linvel(Vector2::new(lin_vel.x, lin_vel.y), true);
+                body.set_angvel(ang_vel, true);
+            },
+            None => {}
+        }
+    }
+
     pub fn get_physics_data(&self, handle: RigidBodyHandle) -> PhysicsData {
         let rb = self
             .rigid_bodies
@@ -173,6 +322,9 @@ impl World {
             position: pos,
             rotation: rot,
             kin_eng: Some(rb.kinetic_energy()),
+            lin_vel: Vec2::new(rb.linvel().x, rb.linvel().y),
+            ang_vel: rb.angvel(),
+            mass: rb.mass(),
         };
         return data;
     }
@@ -222,36 +374,88 @@ impl World {
         }
     }
 
-    pub fn get_closesd_agent(&self, agent_body_handle: RigidBodyHandle) -> Option<RigidBodyHandle> {
-        let rb = self.rigid_bodies.get(agent_body_handle).unwrap();
-        let pos1 = matric_to_vec2(rb.position().translation);
-        let mut dist = f32::INFINITY;
-        let mut target: RigidBodyHandle = RigidBodyHandle::invalid();
+    pub fn get_object_key(&self, handle: RigidBodyHandle) -> Option<u64> {
+        match self.rigid_bodies.get(handle) {
+            Some(body) => {
+                return Some(body.user_data as u64);
+            },
+            None => {
+                return None;
+            }
+        }
+    }
+
+    //  every body inside the agent's sensors, sensors crossing a seam also look at the far side of the world
+    fn for_each_sensed(&self, agent_body_handle: RigidBodyHandle, bounds: &WorldBounds, mut callback: impl FnMut(RigidBodyHandle)) {
+        let rb = match self.rigid_bodies.get(agent_body_handle) {
+            Some(body) => body,
+            None => {
+                return;
+            }
+        };
+        let pos = matric_to_vec2(rb.position().translation);
         for c in rb.colliders() {
             let collider = self.colliders.get(*c).unwrap();
             if !collider.is_sensor() {
                 continue;
             }
-            let filter = QueryFilter { 
-                flags: QueryFilterFlags::ONLY_DYNAMIC | QueryFilterFlags::EXCLUDE_SENSORS, 
-                groups: None, 
-                exclude_collider: Some(*c), 
-                exclude_rigid_body: Some(agent_body_handle), 
+            let filter = QueryFilter {
+                flags: QueryFilterFlags::ONLY_DYNAMIC | QueryFilterFlags::EXCLUDE_SENSORS,
+                groups: None,
+                exclude_collider: Some(*c),
+                exclude_rigid_body: Some(agent_body_handle),
                 ..Default::default()
             };
-            self.query_pipeline.intersections_with_shape(&self.rigid_bodies, &self.colliders, rb.position(), collider.shape(), filter, |collided| {
-                    let rb2_handle = self.get_body_handle_from_collider(collided).unwrap();
-                    let rb2 = self.rigid_bodies.get(rb2_handle).unwrap();
-                    let pos2 = matric_to_vec2(rb2.position().translation);
-                    let new_dist = pos1.distance(pos2);
-                    if new_dist < dist {
-                        dist = new_dist;
-                        target = rb2_handle;
+            let range = collider.shape().compute_local_bounding_sphere().radius();
+            let mut offsets = vec![Vec2::ZERO];
+            offsets.extend(bounds.ghost_offsets(pos, range));
+            for offset in offsets.iter() {
+                let mut iso = *rb.position();
+                iso.translation.vector += Vector2::new(offset.x, offset.y);
+                self.query_pipeline.intersections_with_shape(&self.rigid_bodies, &self.colliders, &iso, collider.shape(), filter, |collided| {
+                        if let Some(rb2_handle) = self.get_body_handle_from_collider(collided) {
+                            let pos2 = self.get_object_position(rb2_handle).unwrap_or(pos);
+                            if self.line_of_sight(pos, pos + bounds.delta(pos, pos2)) {
+                                callback(rb2_handle);
+                            }
+                        }
+                        return true;
                     }
-                    return true;
-                }
-            );
+                );
+            }
         }
+    }
+
+    pub fn get_objects_in_range(&self, agent_body_handle: RigidBodyHandle, bounds: &WorldBounds) -> Vec<u64> {
+        let mut keys: Vec<u64> = vec![];
+        self.for_each_sensed(agent_body_handle, bounds, |rb2_handle| {
+            if let Some(key) = self.get_object_key(rb2_handle) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        });
+        return keys;
+    }
+
+    pub fn get_closesd_agent(&self, agent_body_handle: RigidBodyHandle, bounds: &WorldBounds) -> Option<RigidBodyHandle> {
+        let pos1 = match self.get_object_position(agent_body_handle) {
+            Some(pos) => pos,
+            None => {
+                return None;
+            }
+        };
+        let mut dist = f32::INFINITY;
+        let mut target: RigidBodyHandle = RigidBodyHandle::invalid();
+        self.for_each_sensed(agent_body_handle, bounds, |rb2_handle| {
+            let rb2 = self.rigid_bodies.get(rb2_handle).unwrap();
+            let pos2 = matric_to_vec2(rb2.position().translation);
+            let new_dist = bounds.distance(pos1, pos2);
+            if new_dist < dist {
+                dist = new_dist;
+                target = rb2_handle;
+            }
+        });
         if dist < f32::INFINITY {
             return  Some(target);
         } else {
@@ -264,4 +468,7 @@ pub struct PhysicsData {
     pub position: Vec2,
     pub rotation: f32,
     pub kin_eng: Option<f32>,
+    pub lin_vel: Vec2,
+    pub ang_vel: f32,
+    pub mass: f32,
 }